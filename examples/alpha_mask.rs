@@ -0,0 +1,81 @@
+use bevy::{
+    prelude::{shape::Quad, *},
+    render::render_resource::{Extent3d, TextureDimension, TextureFormat},
+};
+use bevy_outline::{Outline, OutlinePlugin, OutlineSettings, OutlineType};
+
+fn main() {
+    App::new()
+        .add_plugins(DefaultPlugins)
+        .add_plugin(OutlinePlugin)
+        .add_startup_system(setup)
+        .run();
+}
+
+fn setup(
+    mut commands: Commands,
+    mut images: ResMut<Assets<Image>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    commands.spawn((
+        Camera3dBundle {
+            transform: Transform::from_xyz(0.0, 0.0, 4.0).looking_at(Vec3::ZERO, Vec3::Y),
+            ..default()
+        },
+        OutlineSettings {
+            size: 6.0,
+            intensity: 1.0,
+            outline_type: OutlineType::MaxFilter,
+            ..default()
+        },
+    ));
+
+    let mesh = meshes.add(Quad::new(Vec2::splat(1.5)).into());
+    let material = materials.add(StandardMaterial {
+        base_color_texture: Some(images.add(grid_image(128, 16))),
+        alpha_mode: AlphaMode::Mask(0.5),
+        unlit: true,
+        double_sided: true,
+        cull_mode: None,
+        ..default()
+    });
+
+    // Without the alpha mask, the whole quad is outlined
+    for (x, alpha_mask) in [(-1.0, false), (1.0, true)] {
+        commands.spawn((
+            PbrBundle {
+                mesh: mesh.clone(),
+                material: material.clone(),
+                transform: Transform::from_xyz(x, 0.0, 0.0),
+                ..default()
+            },
+            Outline {
+                color: Color::ORANGE,
+                alpha_mask,
+                ..default()
+            },
+        ));
+    }
+}
+
+/// A grid of opaque lines, the cells are transparent
+fn grid_image(size: u32, cell_size: u32) -> Image {
+    let data = (0..size * size)
+        .flat_map(|i| {
+            let (x, y) = (i % size % cell_size, i / size % cell_size);
+            let alpha = if x < 3 || y < 3 { 255 } else { 0 };
+            [255, 255, 255, alpha]
+        })
+        .collect();
+    Image::new(
+        Extent3d {
+            width: size,
+            height: size,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        data,
+        TextureFormat::Rgba8UnormSrgb,
+    )
+}
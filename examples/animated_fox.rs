@@ -3,7 +3,9 @@
 use std::f32::consts::PI;
 
 use bevy::prelude::*;
-use bevy_outline::{Outline, OutlinePlugin, OutlineSettings, OutlineType};
+use bevy_outline::{
+    Outline, OutlineDownsample, OutlinePlugin, OutlineSettings, OutlineTree, OutlineType,
+};
 
 fn main() {
     App::new()
@@ -21,9 +23,6 @@ fn main() {
 #[derive(Resource)]
 struct Animations(Vec<Handle<AnimationClip>>);
 
-#[derive(Component)]
-struct Ground;
-
 fn setup(
     mut commands: Commands,
     asset_server: Res<AssetServer>,
@@ -48,21 +47,21 @@ fn setup(
             size: 32.0,
             intensity: 1.5,
             outline_type: OutlineType::BoxBlur,
+            // Wide glows don't need to be blurred at full resolution
+            downsample: OutlineDownsample::Half,
+            ..default()
         },
     ));
 
     // Plane
-    commands.spawn((
-        PbrBundle {
-            mesh: meshes.add(Mesh::from(shape::Plane {
-                size: 500000.0,
-                subdivisions: 1,
-            })),
-            material: materials.add(Color::rgb(0.3, 0.5, 0.3).into()),
-            ..default()
-        },
-        Ground,
-    ));
+    commands.spawn(PbrBundle {
+        mesh: meshes.add(Mesh::from(shape::Plane {
+            size: 500000.0,
+            subdivisions: 1,
+        })),
+        material: materials.add(Color::rgb(0.3, 0.5, 0.3).into()),
+        ..default()
+    });
 
     // Light
     commands.spawn(DirectionalLightBundle {
@@ -74,20 +73,25 @@ fn setup(
         ..default()
     });
 
-    // Fox
-    commands.spawn(SceneBundle {
-        scene: asset_server.load("Fox.glb#Scene0"),
-        ..default()
-    });
+    // Fox, the outline is copied to the meshes of the scene once it is spawned
+    commands.spawn((
+        SceneBundle {
+            scene: asset_server.load("Fox.glb#Scene0"),
+            ..default()
+        },
+        Outline {
+            color: Color::RED,
+            ..default()
+        },
+        OutlineTree,
+    ));
 }
 
 // Once the scene is loaded, start the animation
 fn setup_scene_once_loaded(
-    mut commands: Commands,
     animations: Res<Animations>,
     mut player: Query<&mut AnimationPlayer>,
     mut done: Local<bool>,
-    mesh: Query<Entity, (With<Handle<Mesh>>, Without<Ground>)>,
 ) {
     if *done {
         return;
@@ -97,10 +101,6 @@ fn setup_scene_once_loaded(
         return;
     };
 
-    for e in &mesh {
-        commands.entity(e).insert(Outline { color: Color::RED });
-    }
-
     player.play(animations.0[0].clone_weak()).repeat();
 
     *done = true;
@@ -0,0 +1,81 @@
+use bevy::prelude::{shape::Cube, *};
+use bevy_outline::{Outline, OutlineAnimation, OutlinePlugin, OutlineSettings, OutlineType};
+
+fn main() {
+    App::new()
+        .add_plugins(DefaultPlugins)
+        .add_plugin(OutlinePlugin)
+        .add_startup_system(setup)
+        .add_system(rotate)
+        .run();
+}
+
+fn setup(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    commands.spawn(PointLightBundle {
+        point_light: PointLight {
+            intensity: 1500.0,
+            shadows_enabled: true,
+            ..default()
+        },
+        transform: Transform::from_xyz(4.0, 8.0, 4.0),
+        ..default()
+    });
+    commands.spawn((
+        Camera3dBundle {
+            transform: Transform::from_xyz(0.0, 2.5, 7.0).looking_at(Vec3::ZERO, Vec3::Y),
+            ..default()
+        },
+        OutlineSettings {
+            size: 12.0,
+            intensity: 1.0,
+            outline_type: OutlineType::Jfa,
+            ..default()
+        },
+    ));
+
+    let mesh = meshes.add(Cube { size: 1.0 }.into());
+    let material = materials.add(Color::GRAY.into());
+
+    // The animations are evaluated in the shaders, no system has to update the outlines
+    let animations = [
+        OutlineAnimation {
+            pulse_amplitude: 0.8,
+            pulse_frequency: 1.0,
+            ..default()
+        },
+        OutlineAnimation {
+            dash_length: 8.0,
+            dash_speed: 24.0,
+            ..default()
+        },
+        OutlineAnimation {
+            hue_speed: 0.25,
+            ..default()
+        },
+    ];
+    for (x, animation) in [-2.0, 0.0, 2.0].into_iter().zip(animations) {
+        commands.spawn((
+            PbrBundle {
+                mesh: mesh.clone(),
+                material: material.clone(),
+                transform: Transform::from_xyz(x, 0.0, 0.0),
+                ..default()
+            },
+            Outline {
+                color: Color::RED,
+                ..default()
+            },
+            animation,
+        ));
+    }
+}
+
+fn rotate(time: Res<Time>, mut query: Query<&mut Transform, With<Outline>>) {
+    for mut transform in &mut query {
+        transform.rotate_y(time.delta_seconds() / 2.0);
+    }
+}
@@ -45,7 +45,10 @@ fn setup(
             ..Default::default()
         },
         RotationAxis(Vec3::Y),
-        Outline { color: Color::BLUE },
+        Outline {
+            color: Color::BLUE,
+            ..default()
+        },
     ));
 
     commands.spawn((
@@ -57,6 +60,7 @@ fn setup(
         RotationAxis(Vec3::X),
         Outline {
             color: Color::GREEN,
+            ..default()
         },
     ));
 
@@ -68,7 +72,10 @@ fn setup(
             ..Default::default()
         },
         RotationAxis(Vec3::Z),
-        Outline { color: Color::RED },
+        Outline {
+            color: Color::RED,
+            ..default()
+        },
     ));
 }
 
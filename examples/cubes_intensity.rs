@@ -47,7 +47,10 @@ fn setup(
             transform: Transform::from_xyz(-1.25, 0.0, 0.5),
             ..Default::default()
         },
-        Outline { color: Color::BLUE },
+        Outline {
+            color: Color::BLUE,
+            ..default()
+        },
     ));
 
     commands.spawn((
@@ -58,6 +61,7 @@ fn setup(
         },
         Outline {
             color: Color::GREEN,
+            ..default()
         },
     ));
 
@@ -68,7 +72,10 @@ fn setup(
             transform: Transform::from_xyz(1.5, 0.0, 0.0),
             ..Default::default()
         },
-        Outline { color: Color::RED },
+        Outline {
+            color: Color::RED,
+            ..default()
+        },
     ));
 }
 
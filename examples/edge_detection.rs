@@ -0,0 +1,133 @@
+use bevy::{
+    core_pipeline::prepass::{DepthPrepass, NormalPrepass},
+    prelude::{
+        shape::{Cube, Plane, Torus, UVSphere},
+        *,
+    },
+};
+use bevy_outline::{
+    Outline, OutlineEdgeDetection, OutlineLayer, OutlineLayers, OutlinePlugin, OutlineSettings,
+    OutlineType,
+};
+
+fn main() {
+    App::new()
+        .add_plugins(DefaultPlugins)
+        .add_plugin(OutlinePlugin)
+        .add_startup_system(setup)
+        .add_system(toggle_thresholds)
+        .run();
+}
+
+fn setup(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    commands.spawn(PointLightBundle {
+        point_light: PointLight {
+            intensity: 1500.0,
+            shadows_enabled: true,
+            ..default()
+        },
+        transform: Transform::from_xyz(4.0, 8.0, 4.0),
+        ..default()
+    });
+
+    // The edges of the whole scene are drawn on the default layer,
+    // the selected entity gets a glow on top of them with a second layer
+    let selection_layer = OutlineLayer(1);
+    commands.spawn((
+        Camera3dBundle {
+            transform: Transform::from_xyz(-3.0, 4.0, 6.0).looking_at(Vec3::ZERO, Vec3::Y),
+            ..default()
+        },
+        DepthPrepass,
+        NormalPrepass,
+        OutlineSettings {
+            size: 2.0,
+            outline_type: OutlineType::EdgeDetect,
+            edge_detection: OutlineEdgeDetection {
+                color: Color::rgb(0.1, 0.05, 0.0),
+                ..default()
+            },
+            ..default()
+        },
+        OutlineLayers(
+            [(
+                selection_layer,
+                OutlineSettings {
+                    size: 16.0,
+                    intensity: 1.0,
+                    outline_type: OutlineType::GaussianBlur,
+                    ..default()
+                },
+            )]
+            .into_iter()
+            .collect(),
+        ),
+    ));
+
+    commands.spawn(PbrBundle {
+        mesh: meshes.add(Plane::from_size(10.0).into()),
+        material: materials.add(Color::rgb(0.9, 0.85, 0.7).into()),
+        ..default()
+    });
+
+    let material = materials.add(Color::rgb(0.8, 0.6, 0.4).into());
+    commands.spawn(PbrBundle {
+        mesh: meshes.add(Cube { size: 1.0 }.into()),
+        material: material.clone(),
+        transform: Transform::from_xyz(-1.5, 0.5, 0.0),
+        ..default()
+    });
+    commands.spawn(PbrBundle {
+        mesh: meshes.add(Torus::default().into()),
+        material: material.clone(),
+        transform: Transform::from_xyz(1.5, 0.25, 0.0),
+        ..default()
+    });
+    commands.spawn((
+        PbrBundle {
+            mesh: meshes.add(UVSphere::default().into()),
+            material,
+            transform: Transform::from_xyz(0.0, 1.0, -1.5),
+            ..default()
+        },
+        Outline {
+            color: Color::CYAN,
+            layer: selection_layer,
+            ..default()
+        },
+    ));
+}
+
+/// Press D, N or C to toggle the depth, normal or color edges
+fn toggle_thresholds(
+    keyboard_input: Res<Input<KeyCode>>,
+    mut settings: Query<&mut OutlineSettings>,
+) {
+    let defaults = OutlineEdgeDetection::default();
+    let toggle = |threshold: &mut f32, default: f32| {
+        *threshold = if *threshold > 0.0 { 0.0 } else { default };
+    };
+    for mut settings in &mut settings {
+        let edge_detection = &mut settings.edge_detection;
+        if keyboard_input.just_pressed(KeyCode::D) {
+            toggle(
+                &mut edge_detection.depth_threshold,
+                defaults.depth_threshold,
+            );
+        } else if keyboard_input.just_pressed(KeyCode::N) {
+            toggle(
+                &mut edge_detection.normal_threshold,
+                defaults.normal_threshold,
+            );
+        } else if keyboard_input.just_pressed(KeyCode::C) {
+            toggle(&mut edge_detection.color_threshold, 0.2);
+        } else {
+            continue;
+        }
+        info!("{:?}", edge_detection);
+    }
+}
@@ -0,0 +1,73 @@
+use bevy::{
+    core_pipeline::{bloom::BloomSettings, tonemapping::Tonemapping},
+    prelude::{shape::UVSphere, *},
+};
+use bevy_outline::{Outline, OutlinePlugin, OutlineSettings, OutlineType};
+
+fn main() {
+    App::new()
+        .add_plugins(DefaultPlugins)
+        .add_plugin(OutlinePlugin)
+        .add_startup_system(setup)
+        .run();
+}
+
+fn setup(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    commands.spawn(PointLightBundle {
+        point_light: PointLight {
+            intensity: 1500.0,
+            ..default()
+        },
+        transform: Transform::from_xyz(4.0, 8.0, 4.0),
+        ..default()
+    });
+    // Outline colors above 1.0 are only kept by HDR cameras, the bloom makes them glow
+    commands.spawn((
+        Camera3dBundle {
+            camera: Camera {
+                hdr: true,
+                ..default()
+            },
+            tonemapping: Tonemapping::TonyMcMapface,
+            transform: Transform::from_xyz(0.0, 2.0, 6.0).looking_at(Vec3::ZERO, Vec3::Y),
+            ..default()
+        },
+        BloomSettings::default(),
+        OutlineSettings {
+            size: 4.0,
+            outline_type: OutlineType::Jfa,
+            ..default()
+        },
+    ));
+
+    let mesh = meshes.add(
+        UVSphere {
+            radius: 0.5,
+            ..default()
+        }
+        .into(),
+    );
+    let material = materials.add(Color::rgb(0.1, 0.1, 0.1).into());
+    for (i, color) in [
+        Color::rgb(4.0, 1.0, 0.2),
+        Color::rgb(0.2, 4.0, 1.0),
+        Color::rgb(1.0, 0.2, 4.0),
+    ]
+    .into_iter()
+    .enumerate()
+    {
+        commands.spawn((
+            PbrBundle {
+                mesh: mesh.clone(),
+                material: material.clone(),
+                transform: Transform::from_xyz(i as f32 * 1.5 - 1.5, 0.0, 0.0),
+                ..default()
+            },
+            Outline { color, ..default() },
+        ));
+    }
+}
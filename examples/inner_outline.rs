@@ -0,0 +1,74 @@
+use bevy::prelude::{shape::Cube, *};
+use bevy_outline::{Outline, OutlinePlugin, OutlineSettings, OutlineSide, OutlineType};
+
+fn main() {
+    App::new()
+        .add_plugins(DefaultPlugins)
+        .add_plugin(OutlinePlugin)
+        .add_startup_system(setup)
+        .add_system(rotate)
+        .add_system(cycle_side)
+        .run();
+}
+
+fn setup(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    commands.spawn(PointLightBundle {
+        point_light: PointLight {
+            intensity: 1500.0,
+            shadows_enabled: true,
+            ..default()
+        },
+        transform: Transform::from_xyz(4.0, 8.0, 4.0),
+        ..default()
+    });
+    commands.spawn((
+        Camera3dBundle {
+            transform: Transform::from_xyz(0.0, 2.5, 5.0).looking_at(Vec3::ZERO, Vec3::Y),
+            ..default()
+        },
+        OutlineSettings {
+            size: 8.0,
+            intensity: 1.0,
+            outline_type: OutlineType::Jfa,
+            side: OutlineSide::Both,
+            ..default()
+        },
+    ));
+
+    commands.spawn((
+        PbrBundle {
+            mesh: meshes.add(Cube { size: 1.5 }.into()),
+            material: materials.add(Color::GRAY.into()),
+            ..default()
+        },
+        Outline {
+            color: Color::CYAN,
+            ..default()
+        },
+    ));
+}
+
+fn rotate(time: Res<Time>, mut query: Query<&mut Transform, With<Outline>>) {
+    for mut transform in &mut query {
+        transform.rotate_y(time.delta_seconds() / 2.0);
+    }
+}
+
+/// Press space to switch between the sides of the outline
+fn cycle_side(keyboard_input: Res<Input<KeyCode>>, mut settings: Query<&mut OutlineSettings>) {
+    if !keyboard_input.just_pressed(KeyCode::Space) {
+        return;
+    }
+    for mut settings in &mut settings {
+        settings.side = match settings.side {
+            OutlineSide::Outer => OutlineSide::Inner,
+            OutlineSide::Inner => OutlineSide::Both,
+            OutlineSide::Both => OutlineSide::Outer,
+        };
+        info!("{:?}", settings.side);
+    }
+}
@@ -0,0 +1,93 @@
+use bevy::{
+    core_pipeline::prepass::{DepthPrepass, NormalPrepass},
+    prelude::{
+        shape::{Box, Cube, Plane},
+        *,
+    },
+};
+use bevy_outline::{Outline, OutlinePlugin, OutlineSettings, OutlineStyle, OutlineType};
+
+fn main() {
+    App::new()
+        .add_plugins(DefaultPlugins)
+        .add_plugin(OutlinePlugin)
+        .add_startup_system(setup)
+        .add_system(toggle_interior_lines)
+        .run();
+}
+
+fn setup(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    commands.spawn(PointLightBundle {
+        point_light: PointLight {
+            intensity: 1500.0,
+            shadows_enabled: true,
+            ..default()
+        },
+        transform: Transform::from_xyz(4.0, 8.0, 4.0),
+        ..default()
+    });
+    // The interior lines need the prepass textures
+    commands.spawn((
+        Camera3dBundle {
+            transform: Transform::from_xyz(-2.0, 3.0, 5.0).looking_at(Vec3::ZERO, Vec3::Y),
+            ..default()
+        },
+        DepthPrepass,
+        NormalPrepass,
+        OutlineSettings {
+            size: 4.0,
+            outline_type: OutlineType::MaxFilter,
+            style: OutlineStyle::Solid { softness: 1.0 },
+            ..default()
+        },
+    ));
+
+    // The outlined boxes have the same color as the ground, only their lines separate them
+    let material = materials.add(Color::rgb(0.8, 0.8, 0.8).into());
+    commands.spawn(PbrBundle {
+        mesh: meshes.add(Plane::from_size(10.0).into()),
+        material: material.clone(),
+        ..default()
+    });
+    commands.spawn((
+        PbrBundle {
+            mesh: meshes.add(Cube { size: 1.0 }.into()),
+            material: material.clone(),
+            transform: Transform::from_xyz(-1.0, 0.5, 0.0),
+            ..default()
+        },
+        Outline {
+            color: Color::ORANGE_RED,
+            interior_lines: true,
+            ..default()
+        },
+    ));
+    commands.spawn((
+        PbrBundle {
+            mesh: meshes.add(Box::new(1.5, 0.5, 1.0).into()),
+            material,
+            transform: Transform::from_xyz(1.0, 0.25, 0.5)
+                .with_rotation(Quat::from_rotation_y(0.6)),
+            ..default()
+        },
+        Outline {
+            color: Color::ORANGE_RED,
+            interior_lines: true,
+            ..default()
+        },
+    ));
+}
+
+/// Press space to toggle the interior lines
+fn toggle_interior_lines(keyboard_input: Res<Input<KeyCode>>, mut outlines: Query<&mut Outline>) {
+    if !keyboard_input.just_pressed(KeyCode::Space) {
+        return;
+    }
+    for mut outline in &mut outlines {
+        outline.interior_lines = !outline.interior_lines;
+    }
+}
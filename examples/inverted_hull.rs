@@ -0,0 +1,80 @@
+use bevy::prelude::{
+    shape::{Capsule, Torus, UVSphere},
+    *,
+};
+use bevy_outline::{Outline, OutlinePlugin, OutlineSettings, OutlineType, OutlineWidthUnit};
+
+fn main() {
+    App::new()
+        .add_plugins(DefaultPlugins)
+        .add_plugin(OutlinePlugin)
+        .add_startup_system(setup)
+        .add_system(cycle_width_unit)
+        .run();
+}
+
+fn setup(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    commands.spawn(PointLightBundle {
+        point_light: PointLight {
+            intensity: 1500.0,
+            shadows_enabled: true,
+            ..default()
+        },
+        transform: Transform::from_xyz(4.0, 8.0, 4.0),
+        ..default()
+    });
+    commands.spawn((
+        Camera3dBundle {
+            transform: Transform::from_xyz(0.0, 3.0, 8.0).looking_at(Vec3::ZERO, Vec3::Y),
+            ..default()
+        },
+        OutlineSettings {
+            size: 6.0,
+            outline_type: OutlineType::InvertedHull,
+            ..default()
+        },
+    ));
+
+    let material = materials.add(Color::GRAY.into());
+    let meshes = [
+        meshes.add(UVSphere::default().into()),
+        meshes.add(Torus::default().into()),
+        meshes.add(Capsule::default().into()),
+    ];
+    for (z, mesh) in [2.0, 0.0, -2.0].into_iter().zip(meshes) {
+        commands.spawn((
+            PbrBundle {
+                mesh,
+                material: material.clone(),
+                transform: Transform::from_xyz(z, 0.0, z),
+                ..default()
+            },
+            Outline {
+                color: Color::BLACK,
+                ..default()
+            },
+        ));
+    }
+}
+
+/// Press space to cycle between outlines in physical pixels, logical pixels and world units
+fn cycle_width_unit(
+    keyboard_input: Res<Input<KeyCode>>,
+    mut settings: Query<&mut OutlineSettings>,
+) {
+    if !keyboard_input.just_pressed(KeyCode::Space) {
+        return;
+    }
+    for mut settings in &mut settings {
+        (settings.width_unit, settings.size) = match settings.width_unit {
+            OutlineWidthUnit::PhysicalPixels => (OutlineWidthUnit::LogicalPixels, 6.0),
+            OutlineWidthUnit::LogicalPixels => (OutlineWidthUnit::World, 0.05),
+            OutlineWidthUnit::World => (OutlineWidthUnit::PhysicalPixels, 6.0),
+        };
+        info!("{:?}", settings.width_unit);
+    }
+}
@@ -0,0 +1,83 @@
+use bevy::prelude::{
+    shape::{Capsule, Cube},
+    *,
+};
+use bevy_outline::{Outline, OutlineKernelShape, OutlinePlugin, OutlineSettings, OutlineType};
+
+fn main() {
+    App::new()
+        .add_plugins(DefaultPlugins)
+        .add_plugin(OutlinePlugin)
+        .add_startup_system(setup)
+        .add_system(cycle_settings)
+        .run();
+}
+
+fn setup(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    commands.spawn(PointLightBundle {
+        point_light: PointLight {
+            intensity: 1500.0,
+            shadows_enabled: true,
+            ..default()
+        },
+        transform: Transform::from_xyz(4.0, 8.0, 4.0),
+        ..default()
+    });
+    commands.spawn((
+        Camera3dBundle {
+            transform: Transform::from_xyz(0.0, 1.0, 5.0).looking_at(Vec3::ZERO, Vec3::Y),
+            ..default()
+        },
+        OutlineSettings {
+            size: 16.0,
+            intensity: 1.0,
+            outline_type: OutlineType::MaxFilter,
+            ..default()
+        },
+    ));
+
+    let material = materials.add(Color::GRAY.into());
+    let meshes = [
+        meshes.add(Capsule::default().into()),
+        meshes.add(Cube { size: 1.0 }.into()),
+    ];
+    for (x, mesh) in [-1.0, 1.0].into_iter().zip(meshes) {
+        commands.spawn((
+            PbrBundle {
+                mesh,
+                material: material.clone(),
+                transform: Transform::from_xyz(x, 0.0, 0.0),
+                ..default()
+            },
+            Outline {
+                color: Color::ORANGE,
+                ..default()
+            },
+        ));
+    }
+}
+
+/// Press space to switch between the kernel shapes and T to switch between the outline types
+fn cycle_settings(keyboard_input: Res<Input<KeyCode>>, mut settings: Query<&mut OutlineSettings>) {
+    for mut settings in &mut settings {
+        if keyboard_input.just_pressed(KeyCode::Space) {
+            settings.kernel_shape = match settings.kernel_shape {
+                OutlineKernelShape::Square => OutlineKernelShape::Circle,
+                OutlineKernelShape::Circle => OutlineKernelShape::Diamond,
+                OutlineKernelShape::Diamond => OutlineKernelShape::Square,
+            };
+            info!("{:?}", settings.kernel_shape);
+        }
+        if keyboard_input.just_pressed(KeyCode::T) {
+            settings.outline_type = match settings.outline_type {
+                OutlineType::MaxFilter => OutlineType::Jfa,
+                _ => OutlineType::MaxFilter,
+            };
+            info!("{:?}", settings.outline_type);
+        }
+    }
+}
@@ -0,0 +1,88 @@
+use bevy::{
+    prelude::{shape::Cube, *},
+    utils::HashMap,
+};
+use bevy_outline::{
+    Outline, OutlineLayer, OutlineLayers, OutlinePlugin, OutlineSettings, OutlineStyle, OutlineType,
+};
+
+const SELECTED: OutlineLayer = OutlineLayer(0);
+const HOVERED: OutlineLayer = OutlineLayer(1);
+
+fn main() {
+    App::new()
+        .add_plugins(DefaultPlugins)
+        .add_plugin(OutlinePlugin)
+        .add_startup_system(setup)
+        .add_system(rotate)
+        .run();
+}
+
+fn setup(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    commands.spawn(PointLightBundle {
+        point_light: PointLight {
+            intensity: 1500.0,
+            shadows_enabled: true,
+            ..default()
+        },
+        transform: Transform::from_xyz(4.0, 8.0, 4.0),
+        ..default()
+    });
+    commands.spawn((
+        Camera3dBundle {
+            transform: Transform::from_xyz(0.0, 2.5, 6.0).looking_at(Vec3::ZERO, Vec3::Y),
+            ..default()
+        },
+        // Each layer is drawn with its own settings
+        OutlineLayers(HashMap::from_iter([
+            (
+                SELECTED,
+                OutlineSettings {
+                    size: 3.0,
+                    intensity: 1.0,
+                    outline_type: OutlineType::MaxFilter,
+                    style: OutlineStyle::Solid { softness: 1.0 },
+                    ..default()
+                },
+            ),
+            (
+                HOVERED,
+                OutlineSettings {
+                    size: 16.0,
+                    intensity: 1.5,
+                    outline_type: OutlineType::GaussianBlur,
+                    ..default()
+                },
+            ),
+        ])),
+    ));
+
+    let mesh = meshes.add(Cube { size: 1.0 }.into());
+    let material = materials.add(Color::GRAY.into());
+
+    for (x, color, layer) in [(-1.0, Color::WHITE, SELECTED), (1.0, Color::CYAN, HOVERED)] {
+        commands.spawn((
+            PbrBundle {
+                mesh: mesh.clone(),
+                material: material.clone(),
+                transform: Transform::from_xyz(x, 0.0, 0.0),
+                ..default()
+            },
+            Outline {
+                color,
+                layer,
+                ..default()
+            },
+        ));
+    }
+}
+
+fn rotate(time: Res<Time>, mut query: Query<&mut Transform, With<Outline>>) {
+    for mut transform in &mut query {
+        transform.rotate_y(time.delta_seconds() / 2.0);
+    }
+}
@@ -0,0 +1,90 @@
+use bevy::{
+    core_pipeline::prepass::DepthPrepass,
+    prelude::{shape::Cube, *},
+};
+use bevy_outline::{Outline, OutlineOcclusion, OutlinePlugin, OutlineSettings, OutlineType};
+
+fn main() {
+    App::new()
+        .add_plugins(DefaultPlugins)
+        .add_plugin(OutlinePlugin)
+        .add_startup_system(setup)
+        .add_system(move_cubes)
+        .run();
+}
+
+#[derive(Component)]
+struct Moving;
+
+fn setup(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    commands.spawn(PointLightBundle {
+        point_light: PointLight {
+            intensity: 1500.0,
+            shadows_enabled: true,
+            ..default()
+        },
+        transform: Transform::from_xyz(4.0, 8.0, 4.0),
+        ..default()
+    });
+    commands.spawn((
+        Camera3dBundle {
+            transform: Transform::from_xyz(0.0, 2.5, 6.0).looking_at(Vec3::ZERO, Vec3::Y),
+            ..default()
+        },
+        // The depth prepass is required to know which parts of the meshes are hidden
+        DepthPrepass,
+        OutlineSettings {
+            size: 8.0,
+            intensity: 1.0,
+            outline_type: OutlineType::MaxFilter,
+            ..default()
+        },
+    ));
+
+    // Wall
+    commands.spawn(PbrBundle {
+        mesh: meshes.add(shape::Box::new(4.0, 2.0, 0.2).into()),
+        material: materials.add(Color::SILVER.into()),
+        transform: Transform::from_xyz(0.0, 0.0, 1.0),
+        ..default()
+    });
+
+    let mesh = meshes.add(Cube { size: 1.0 }.into());
+    let material = materials.add(Color::RED.into());
+
+    for (i, occlusion) in [
+        OutlineOcclusion::AlwaysVisible,
+        OutlineOcclusion::VisibleOnly,
+        OutlineOcclusion::XRay {
+            color: Color::YELLOW,
+        },
+    ]
+    .into_iter()
+    .enumerate()
+    {
+        commands.spawn((
+            PbrBundle {
+                mesh: mesh.clone(),
+                material: material.clone(),
+                transform: Transform::from_xyz(0.0, i as f32 * 1.5 - 1.5, 0.0),
+                ..default()
+            },
+            Outline {
+                color: Color::GREEN,
+                occlusion,
+                ..default()
+            },
+            Moving,
+        ));
+    }
+}
+
+fn move_cubes(time: Res<Time>, mut query: Query<&mut Transform, With<Moving>>) {
+    for mut transform in &mut query {
+        transform.translation.x = time.elapsed_seconds().sin() * 3.0;
+    }
+}
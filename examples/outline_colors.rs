@@ -0,0 +1,81 @@
+use bevy::prelude::{shape::UVSphere, *};
+use bevy_outline::{Outline, OutlinePlugin, OutlineSettings, OutlineType};
+
+fn main() {
+    App::new()
+        .insert_resource(ClearColor(Color::rgb(0.8, 0.8, 0.8)))
+        .add_plugins(DefaultPlugins)
+        .add_plugin(OutlinePlugin)
+        .add_startup_system(setup)
+        .add_system(cycle_outline_type)
+        .run();
+}
+
+fn setup(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    commands.spawn(PointLightBundle {
+        point_light: PointLight {
+            intensity: 1500.0,
+            shadows_enabled: true,
+            ..default()
+        },
+        transform: Transform::from_xyz(4.0, 8.0, 4.0),
+        ..default()
+    });
+    commands.spawn((
+        Camera3dBundle {
+            transform: Transform::from_xyz(0.0, 0.0, 6.0).looking_at(Vec3::ZERO, Vec3::Y),
+            ..default()
+        },
+        OutlineSettings {
+            size: 8.0,
+            intensity: 1.0,
+            outline_type: OutlineType::MaxFilter,
+            ..default()
+        },
+    ));
+
+    let mesh = meshes.add(UVSphere::default().into());
+    let material = materials.add(Color::WHITE.into());
+
+    // Black and dark outlines don't bleed over the spheres
+    let colors = [
+        Color::BLACK,
+        Color::rgb(0.1, 0.0, 0.2),
+        Color::rgba(0.0, 0.0, 1.0, 0.5),
+    ];
+    for (x, color) in [-2.5, 0.0, 2.5].into_iter().zip(colors) {
+        commands.spawn((
+            PbrBundle {
+                mesh: mesh.clone(),
+                material: material.clone(),
+                transform: Transform::from_xyz(x, 0.0, 0.0),
+                ..default()
+            },
+            Outline { color, ..default() },
+        ));
+    }
+}
+
+/// Press space to switch between the outline types
+fn cycle_outline_type(
+    keyboard_input: Res<Input<KeyCode>>,
+    mut settings: Query<&mut OutlineSettings>,
+) {
+    if !keyboard_input.just_pressed(KeyCode::Space) {
+        return;
+    }
+    for mut settings in &mut settings {
+        settings.outline_type = match settings.outline_type {
+            OutlineType::MaxFilter => OutlineType::BoxBlur,
+            OutlineType::BoxBlur => OutlineType::GaussianBlur,
+            OutlineType::GaussianBlur => OutlineType::Jfa,
+            OutlineType::Jfa => OutlineType::InvertedHull,
+            OutlineType::InvertedHull | OutlineType::EdgeDetect => OutlineType::MaxFilter,
+        };
+        info!("{:?}", settings.outline_type);
+    }
+}
@@ -0,0 +1,114 @@
+use bevy::prelude::{
+    shape::{Box, Cylinder, Plane},
+    *,
+};
+use bevy_outline::{
+    Outline, OutlineGroup, OutlineOverlap, OutlinePlugin, OutlineSettings, OutlineStyle,
+    OutlineType,
+};
+
+fn main() {
+    App::new()
+        .add_plugins(DefaultPlugins)
+        .add_plugin(OutlinePlugin)
+        .add_startup_system(setup)
+        .add_system(toggle_groups)
+        .run();
+}
+
+/// The group of a part when the parts are outlined separately
+#[derive(Component)]
+struct Part(u32);
+
+fn setup(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    commands.spawn(PointLightBundle {
+        point_light: PointLight {
+            intensity: 1500.0,
+            shadows_enabled: true,
+            ..default()
+        },
+        transform: Transform::from_xyz(4.0, 8.0, 4.0),
+        ..default()
+    });
+    commands.spawn((
+        Camera3dBundle {
+            transform: Transform::from_xyz(-2.0, 3.0, 5.0).looking_at(Vec3::ZERO, Vec3::Y),
+            ..default()
+        },
+        OutlineSettings {
+            size: 4.0,
+            outline_type: OutlineType::MaxFilter,
+            style: OutlineStyle::Solid { softness: 1.0 },
+            ..default()
+        },
+    ));
+
+    commands.spawn(PbrBundle {
+        mesh: meshes.add(Plane::from_size(10.0).into()),
+        material: materials.add(Color::rgb(0.3, 0.5, 0.3).into()),
+        ..default()
+    });
+
+    // An assembly of overlapping parts, all of them are selected
+    let material = materials.add(Color::rgb(0.8, 0.8, 0.8).into());
+    let parts = [
+        (
+            meshes.add(Box::new(2.0, 0.4, 1.2).into()),
+            Transform::from_xyz(0.0, 0.2, 0.0),
+        ),
+        (
+            meshes.add(
+                Cylinder {
+                    radius: 0.25,
+                    height: 1.5,
+                    ..default()
+                }
+                .into(),
+            ),
+            Transform::from_xyz(-0.5, 1.0, 0.2),
+        ),
+        (
+            meshes.add(Box::new(1.0, 0.3, 0.8).into()),
+            Transform::from_xyz(0.4, 0.55, 0.3),
+        ),
+    ];
+    for (group, (mesh, transform)) in parts.into_iter().enumerate() {
+        let group = group as u32;
+        commands.spawn((
+            PbrBundle {
+                mesh,
+                material: material.clone(),
+                transform,
+                ..default()
+            },
+            Outline {
+                color: Color::ORANGE_RED,
+                // The nearest part draws its outline over the parts behind it
+                overlap: OutlineOverlap::Nearest,
+                ..default()
+            },
+            OutlineGroup(group),
+            Part(group),
+        ));
+    }
+}
+
+/// Press space to switch between an outline per part and a single outline around the assembly
+fn toggle_groups(
+    keyboard_input: Res<Input<KeyCode>>,
+    mut parts: Query<(&Part, &mut OutlineGroup)>,
+) {
+    if !keyboard_input.just_pressed(KeyCode::Space) {
+        return;
+    }
+    for (part, mut group) in &mut parts {
+        group.0 = match group.0 {
+            0 => part.0,
+            _ => 0,
+        };
+    }
+}
@@ -0,0 +1,73 @@
+use bevy::prelude::{shape::Cube, *};
+use bevy_outline::{Outline, OutlinePlugin, OutlineSettings, OutlineStyle, OutlineType};
+
+fn main() {
+    App::new()
+        .add_plugins(DefaultPlugins)
+        .add_plugin(OutlinePlugin)
+        .add_startup_system(setup)
+        .add_system(rotate)
+        .run();
+}
+
+fn setup(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    commands.spawn(PointLightBundle {
+        point_light: PointLight {
+            intensity: 1500.0,
+            shadows_enabled: true,
+            ..default()
+        },
+        transform: Transform::from_xyz(4.0, 8.0, 4.0),
+        ..default()
+    });
+    commands.spawn((
+        Camera3dBundle {
+            transform: Transform::from_xyz(0.0, 2.5, 6.0).looking_at(Vec3::ZERO, Vec3::Y),
+            ..default()
+        },
+        // The size is used by every outline without a width and is the maximum width
+        OutlineSettings {
+            size: 24.0,
+            intensity: 1.0,
+            outline_type: OutlineType::Jfa,
+            style: OutlineStyle::Solid { softness: 1.0 },
+            ..default()
+        },
+    ));
+
+    let mesh = meshes.add(Cube { size: 1.0 }.into());
+    let material = materials.add(Color::GRAY.into());
+
+    for (i, (color, width)) in [
+        (Color::BLUE, Some(2.0)),
+        (Color::GREEN, Some(8.0)),
+        (Color::RED, None),
+    ]
+    .into_iter()
+    .enumerate()
+    {
+        commands.spawn((
+            PbrBundle {
+                mesh: mesh.clone(),
+                material: material.clone(),
+                transform: Transform::from_xyz(i as f32 * 2.0 - 2.0, 0.0, 0.0),
+                ..default()
+            },
+            Outline {
+                color,
+                width,
+                ..default()
+            },
+        ));
+    }
+}
+
+fn rotate(time: Res<Time>, mut query: Query<&mut Transform, With<Outline>>) {
+    for mut transform in &mut query {
+        transform.rotate_y(time.delta_seconds() / 2.0);
+    }
+}
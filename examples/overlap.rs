@@ -0,0 +1,77 @@
+use bevy::prelude::{shape::UVSphere, *};
+use bevy_outline::{Outline, OutlineOverlap, OutlinePlugin, OutlineSettings, OutlineType};
+
+fn main() {
+    App::new()
+        .add_plugins(DefaultPlugins)
+        .add_plugin(OutlinePlugin)
+        .add_startup_system(setup)
+        .add_system(cycle_overlap)
+        .run();
+}
+
+fn setup(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    commands.spawn(PointLightBundle {
+        point_light: PointLight {
+            intensity: 1500.0,
+            shadows_enabled: true,
+            ..default()
+        },
+        transform: Transform::from_xyz(4.0, 8.0, 4.0),
+        ..default()
+    });
+    commands.spawn((
+        Camera3dBundle {
+            transform: Transform::from_xyz(0.0, 0.0, 5.0).looking_at(Vec3::ZERO, Vec3::Y),
+            ..default()
+        },
+        OutlineSettings {
+            size: 16.0,
+            intensity: 1.0,
+            outline_type: OutlineType::MaxFilter,
+            ..default()
+        },
+    ));
+
+    let mesh = meshes.add(UVSphere::default().into());
+    let material = materials.add(Color::GRAY.into());
+
+    // The spheres are closer to the camera from left to right
+    let colors = [Color::RED, Color::GREEN, Color::BLUE];
+    for (i, color) in colors.into_iter().enumerate() {
+        let i = i as f32;
+        commands.spawn((
+            PbrBundle {
+                mesh: mesh.clone(),
+                material: material.clone(),
+                transform: Transform::from_xyz(i - 1.0, 0.0, i * 0.5),
+                ..default()
+            },
+            Outline { color, ..default() },
+        ));
+    }
+}
+
+/// Press space to switch between the overlap policies.
+/// With priorities, the furthest sphere has the highest priority.
+fn cycle_overlap(
+    keyboard_input: Res<Input<KeyCode>>,
+    mut outlines: Query<(&mut Outline, &Transform)>,
+) {
+    if !keyboard_input.just_pressed(KeyCode::Space) {
+        return;
+    }
+    for (mut outline, transform) in &mut outlines {
+        let priority = -(transform.translation.z * 2.0) as i32;
+        outline.overlap = match outline.overlap {
+            OutlineOverlap::Nearest => OutlineOverlap::Priority(priority),
+            OutlineOverlap::Priority(_) => OutlineOverlap::Blend,
+            OutlineOverlap::Blend => OutlineOverlap::Nearest,
+        };
+        info!("{:?}", outline.overlap);
+    }
+}
@@ -1,9 +1,14 @@
 use bevy::prelude::{shape::Cube, *};
 use bevy_mod_picking::{
-    HoverEvent, InteractablePickingPlugin, PickableBundle, PickingCameraBundle, PickingEvent,
-    PickingPlugin,
+    InteractablePickingPlugin, PickableBundle, PickingCameraBundle, PickingPlugin,
 };
-use bevy_outline::{Outline, OutlinePlugin, OutlineSettings};
+use bevy_outline::{
+    Outline, OutlineLayer, OutlineLayers, OutlinePlugin, OutlineSelectionPlugin,
+    OutlineSelectionStyles, OutlineSettings, OutlineStyle, OutlineTransition, OutlineType,
+};
+
+/// The hovered and pressed outlines glow, the selected ones are solid
+const SELECTED_LAYER: OutlineLayer = OutlineLayer(1);
 
 fn main() {
     App::new()
@@ -11,8 +16,25 @@ fn main() {
         .add_plugin(PickingPlugin)
         .add_plugin(InteractablePickingPlugin)
         .add_plugin(OutlinePlugin)
+        // Hold left control to select several cubes
+        .add_plugin(OutlineSelectionPlugin {
+            styles: OutlineSelectionStyles {
+                hovered: Some(Outline {
+                    color: Color::GREEN,
+                    ..default()
+                }),
+                pressed: Some(Outline {
+                    color: Color::YELLOW,
+                    ..default()
+                }),
+                selected: Some(Outline {
+                    color: Color::ORANGE,
+                    layer: SELECTED_LAYER,
+                    ..default()
+                }),
+            },
+        })
         .add_startup_system(setup)
-        .add_system(handle_picking)
         .run();
 }
 
@@ -29,16 +51,29 @@ fn setup(
             ..Default::default()
         },
         PickableBundle::default(),
+        // The outlines fade in and out instead of popping
+        OutlineTransition::default(),
     ));
 
-    commands.spawn((
-        PbrBundle {
-            mesh: meshes.add(Cube { size: 1.0 }.into()),
-            material: materials.add(Color::RED.into()),
-            ..Default::default()
-        },
-        PickableBundle::default(),
-    ));
+    // The cube it holds is outlined with it
+    commands
+        .spawn((
+            PbrBundle {
+                mesh: meshes.add(Cube { size: 1.0 }.into()),
+                material: materials.add(Color::RED.into()),
+                ..Default::default()
+            },
+            PickableBundle::default(),
+            OutlineTransition::default(),
+        ))
+        .with_children(|parent| {
+            parent.spawn(PbrBundle {
+                mesh: meshes.add(Cube { size: 0.5 }.into()),
+                material: materials.add(Color::BLUE.into()),
+                transform: Transform::from_xyz(0.0, 0.75, 0.0),
+                ..Default::default()
+            });
+        });
 
     commands.spawn((
         PbrBundle {
@@ -48,6 +83,8 @@ fn setup(
             ..Default::default()
         },
         PickableBundle::default(),
+        // The outlines fade in and out instead of popping
+        OutlineTransition::default(),
     ));
 
     commands.spawn(PointLightBundle {
@@ -70,23 +107,18 @@ fn setup(
             intensity: 1.5,
             ..default()
         },
+        OutlineLayers(
+            [(
+                SELECTED_LAYER,
+                OutlineSettings {
+                    size: 4.0,
+                    outline_type: OutlineType::MaxFilter,
+                    style: OutlineStyle::Solid { softness: 1.0 },
+                    ..default()
+                },
+            )]
+            .into(),
+        ),
         PickingCameraBundle::default(),
     ));
 }
-
-pub fn handle_picking(mut commands: Commands, mut events: EventReader<PickingEvent>) {
-    for event in events.iter() {
-        if let PickingEvent::Hover(e) = event {
-            match e {
-                HoverEvent::JustEntered(e) => {
-                    commands.entity(*e).insert(Outline {
-                        color: Color::GREEN,
-                    });
-                }
-                HoverEvent::JustLeft(e) => {
-                    commands.entity(*e).remove::<Outline>();
-                }
-            }
-        }
-    }
-}
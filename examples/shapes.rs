@@ -60,6 +60,7 @@ fn setup(
                     rng.generate_range(0..=100) as f32 / 100.0,
                     rng.generate_range(0..=100) as f32 / 100.0,
                 ),
+                ..default()
             },
         ));
     }
@@ -0,0 +1,109 @@
+use bevy::{
+    core_pipeline::clear_color::ClearColorConfig,
+    prelude::{shape::Cube, *},
+    render::camera::Viewport,
+    window::PrimaryWindow,
+};
+use bevy_outline::{Outline, OutlinePlugin, OutlineSettings, OutlineType};
+
+fn main() {
+    App::new()
+        .add_plugins(DefaultPlugins)
+        .add_plugin(OutlinePlugin)
+        .add_startup_system(setup)
+        .add_system(set_viewports)
+        .run();
+}
+
+/// Index of the half of the window drawn by a camera
+#[derive(Component)]
+struct SplitScreen(u32);
+
+fn setup(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    commands.spawn(PointLightBundle {
+        point_light: PointLight {
+            intensity: 1500.0,
+            ..default()
+        },
+        transform: Transform::from_xyz(4.0, 8.0, 4.0),
+        ..default()
+    });
+    // Each half of the window has its own outline settings
+    commands.spawn((
+        Camera3dBundle {
+            transform: Transform::from_xyz(-2.0, 2.5, 5.0).looking_at(Vec3::ZERO, Vec3::Y),
+            ..default()
+        },
+        OutlineSettings {
+            size: 8.0,
+            outline_type: OutlineType::GaussianBlur,
+            ..default()
+        },
+        SplitScreen(0),
+    ));
+    commands.spawn((
+        Camera3dBundle {
+            camera: Camera {
+                order: 1,
+                ..default()
+            },
+            // The left half was already cleared
+            camera_3d: Camera3d {
+                clear_color: ClearColorConfig::None,
+                ..default()
+            },
+            transform: Transform::from_xyz(2.0, 2.5, 5.0).looking_at(Vec3::ZERO, Vec3::Y),
+            ..default()
+        },
+        OutlineSettings {
+            size: 4.0,
+            outline_type: OutlineType::Jfa,
+            ..default()
+        },
+        SplitScreen(1),
+    ));
+
+    commands.spawn(PbrBundle {
+        mesh: meshes.add(shape::Plane::from_size(5.0).into()),
+        material: materials.add(Color::rgb(0.3, 0.5, 0.3).into()),
+        ..default()
+    });
+    commands.spawn((
+        PbrBundle {
+            mesh: meshes.add(Cube { size: 1.0 }.into()),
+            material: materials.add(Color::rgb(0.8, 0.7, 0.6).into()),
+            transform: Transform::from_xyz(0.0, 0.5, 0.0),
+            ..default()
+        },
+        Outline {
+            color: Color::CYAN,
+            ..default()
+        },
+    ));
+}
+
+/// Keeps each camera on its half of the window when it's resized
+fn set_viewports(
+    windows: Query<&Window, With<PrimaryWindow>>,
+    mut cameras: Query<(&mut Camera, &SplitScreen)>,
+) {
+    let Ok(window) = windows.get_single() else {
+        return;
+    };
+    let size = UVec2::new(window.physical_width() / 2, window.physical_height());
+    // A minimized window has no size
+    if size.cmpeq(UVec2::ZERO).any() {
+        return;
+    }
+    for (mut camera, split_screen) in &mut cameras {
+        camera.viewport = Some(Viewport {
+            physical_position: UVec2::new(split_screen.0 * size.x, 0),
+            physical_size: size,
+            ..default()
+        });
+    }
+}
@@ -0,0 +1,91 @@
+use bevy::{
+    prelude::{shape::RegularPolygon, *},
+    render::render_resource::{Extent3d, TextureDimension, TextureFormat},
+    sprite::MaterialMesh2dBundle,
+};
+use bevy_outline::{Outline, OutlinePlugin, OutlineSettings, OutlineType};
+
+fn main() {
+    App::new()
+        .add_plugins(DefaultPlugins)
+        .add_plugin(OutlinePlugin)
+        .add_startup_system(setup)
+        .add_system(rotate)
+        .run();
+}
+
+fn setup(
+    mut commands: Commands,
+    mut images: ResMut<Assets<Image>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    commands.spawn((
+        Camera2dBundle::default(),
+        OutlineSettings {
+            size: 8.0,
+            intensity: 1.0,
+            outline_type: OutlineType::MaxFilter,
+            ..default()
+        },
+    ));
+
+    // The transparent pixels around the ring are not outlined
+    commands.spawn((
+        SpriteBundle {
+            texture: images.add(ring_image(128)),
+            transform: Transform::from_xyz(-150.0, 0.0, 0.0),
+            ..default()
+        },
+        Outline {
+            color: Color::YELLOW,
+            ..default()
+        },
+    ));
+
+    commands.spawn((
+        MaterialMesh2dBundle {
+            mesh: meshes.add(RegularPolygon::new(64.0, 6).into()).into(),
+            material: materials.add(ColorMaterial::from(Color::PURPLE)),
+            transform: Transform::from_xyz(150.0, 0.0, 0.0),
+            ..default()
+        },
+        Outline {
+            color: Color::CYAN,
+            ..default()
+        },
+    ));
+}
+
+/// A white ring, everything else is transparent
+fn ring_image(size: u32) -> Image {
+    let center = size as f32 / 2.0;
+    let data = (0..size * size)
+        .flat_map(|i| {
+            let position = Vec2::new((i % size) as f32 + 0.5, (i / size) as f32 + 0.5);
+            let distance = position.distance(Vec2::splat(center));
+            let alpha = if distance > center * 0.6 && distance < center {
+                255
+            } else {
+                0
+            };
+            [255, 255, 255, alpha]
+        })
+        .collect();
+    Image::new(
+        Extent3d {
+            width: size,
+            height: size,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        data,
+        TextureFormat::Rgba8UnormSrgb,
+    )
+}
+
+fn rotate(time: Res<Time>, mut query: Query<&mut Transform, With<Outline>>) {
+    for mut transform in &mut query {
+        transform.rotate_z(time.delta_seconds() / 2.0);
+    }
+}
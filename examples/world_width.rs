@@ -0,0 +1,77 @@
+use bevy::prelude::{shape::Cube, *};
+use bevy_outline::{
+    Outline, OutlineFade, OutlinePlugin, OutlineSettings, OutlineType, OutlineWidthUnit,
+};
+
+fn main() {
+    App::new()
+        .add_plugins(DefaultPlugins)
+        .add_plugin(OutlinePlugin)
+        .add_startup_system(setup)
+        .add_system(toggle_width_unit)
+        .run();
+}
+
+fn setup(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    commands.spawn(DirectionalLightBundle {
+        transform: Transform::from_xyz(4.0, 8.0, 4.0).looking_at(Vec3::ZERO, Vec3::Y),
+        ..default()
+    });
+    // The outlines are 5cm wide and fade out between 15 and 30 meters from the camera
+    commands.spawn((
+        Camera3dBundle {
+            transform: Transform::from_xyz(-3.0, 2.0, 4.0)
+                .looking_at(Vec3::new(0.0, 0.0, -10.0), Vec3::Y),
+            ..default()
+        },
+        OutlineSettings {
+            size: 0.05,
+            outline_type: OutlineType::Jfa,
+            width_unit: OutlineWidthUnit::World,
+            fade: Some(OutlineFade {
+                start: 15.0,
+                end: 30.0,
+                ..default()
+            }),
+            ..default()
+        },
+    ));
+
+    let mesh = meshes.add(Cube { size: 1.0 }.into());
+    let material = materials.add(Color::GRAY.into());
+    for i in 0..20 {
+        commands.spawn((
+            PbrBundle {
+                mesh: mesh.clone(),
+                material: material.clone(),
+                transform: Transform::from_xyz(0.0, 0.0, -2.0 * i as f32),
+                ..default()
+            },
+            Outline {
+                color: Color::ORANGE,
+                ..default()
+            },
+        ));
+    }
+}
+
+/// Press space to compare with outlines of the same width on the whole screen
+fn toggle_width_unit(
+    keyboard_input: Res<Input<KeyCode>>,
+    mut settings: Query<&mut OutlineSettings>,
+) {
+    if !keyboard_input.just_pressed(KeyCode::Space) {
+        return;
+    }
+    for mut settings in &mut settings {
+        (settings.width_unit, settings.size) = match settings.width_unit {
+            OutlineWidthUnit::World => (OutlineWidthUnit::LogicalPixels, 4.0),
+            _ => (OutlineWidthUnit::World, 0.05),
+        };
+        info!("{:?}", settings.width_unit);
+    }
+}
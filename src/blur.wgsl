@@ -1,7 +1,6 @@
 struct BlurSettings {
     size: f32,
     dims: vec2<f32>,
-    viewport: vec4<f32>,
 };
 
 @group(0) @binding(0)
@@ -12,7 +11,13 @@ var stencil_sampler: sampler;
 var<uniform> settings: BlurSettings;
 
 fn sample_stencil(uv: vec2<f32>, offset: vec2<f32>) -> vec4<f32> {
-    return textureSample(input_texture, stencil_sampler, uv + offset * settings.dims);
+    let color = textureSample(input_texture, stencil_sampler, uv + offset * settings.dims);
+#ifdef INNER
+    // Inner outlines blur the inverted coverage of the stencil data, the color is added back in the combine pass
+    return vec4(1.0 - color.a);
+#else
+    return color;
+#endif
 }
 
 var<private> OFFSETS_5: array<f32, 1> = array<f32, 1>(
@@ -97,8 +102,6 @@ fn box_blur(uv: vec2<f32>, direction: vec2<f32>) -> vec4<f32>{
 
 @fragment
 fn fragment(@location(0) uv: vec2<f32>) -> @location(0) vec4<f32> {
-    let sample_uv = settings.viewport.xy + uv * settings.viewport.zw;
-
     #ifdef HORIZONTAL
     let direction = vec2(1.0, 0.0);
     #else // HORIZONTAL
@@ -106,10 +109,10 @@ fn fragment(@location(0) uv: vec2<f32>) -> @location(0) vec4<f32> {
     #endif // HORIZONTAL
 
     #ifdef GAUSSIAN_BLUR
-    return gaussian_blur(sample_uv, direction);
-    // return blur13(sample_uv, direction * settings.size);
+    return gaussian_blur(uv, direction);
+    // return blur13(uv, direction * settings.size);
     #else // GAUSSIAN_BLUR
-    return box_blur(sample_uv, direction);
+    return box_blur(uv, direction);
     #endif // GAUSSIAN_BLUR
 }
 
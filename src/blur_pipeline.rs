@@ -41,13 +41,16 @@ pub enum BlurType {
 pub struct BlurUniform {
     pub size: f32,
     pub dims: Vec2,
-    pub viewport: Vec4,
 }
 
 #[derive(PartialEq, Eq, Hash, Clone, Copy)]
 pub struct BlurPipelineKey {
     pub blur_type: BlurType,
     pub direction: BlurDirection,
+    /// Blurs the inverted stencil
+    pub inner: bool,
+    /// The view is HDR, the colors are stored in a float texture
+    pub hdr: bool,
 }
 
 #[derive(Resource)]
@@ -101,13 +104,17 @@ impl SpecializedRenderPipeline for BlurPipeline {
             BlurType::Gaussian => shader_defs.push("GAUSSIAN_BLUR".into()),
         }
 
+        if key.inner {
+            shader_defs.push("INNER".into());
+        }
+
         RenderPipelineDescriptorBuilder::fullscreen()
             .label(format!("{}_blur_pipeline", key.direction))
             .layout(vec![self.layout.clone()])
             .fragment(
                 BLUR_SHADER_HANDLE,
                 "fragment",
-                &[color_target(None)],
+                &[color_target(None, key.hdr)],
                 &shader_defs,
             )
             .build()
@@ -4,23 +4,82 @@ var stencil_sampler: sampler;
 var stencil: texture_2d<f32>;
 @group(0) @binding(2)
 var blur_texture: texture_2d<f32>;
+struct CombineSettings {
+    intensity: f32,
+    solid: u32,
+    softness: f32,
+    outer: u32,
+    inner: u32,
+};
+
 @group(0) @binding(3)
-var<uniform> intensity: f32;
+var<uniform> settings: CombineSettings;
+// Only the alpha is used, the color comes from the stencil
+@group(0) @binding(4)
+var inner_texture: texture_2d<f32>;
+// The alpha is the coverage of the stencil
+@group(0) @binding(5)
+var stencil_data: texture_2d<f32>;
+#ifdef INTERIOR_LINES
+// Premultiplied lines inside the stencil, see Outline::interior_lines
+@group(0) @binding(6)
+var interior_lines_texture: texture_2d<f32>;
+#endif
+
+// Anything fainter than this isn't part of a solid outline
+const SOLID_THRESHOLD: f32 = 0.01;
 
-// TODO solid outlines
+// Thresholds the outline into a fully opaque band with an antialiased edge
+fn solid_outline(blur_color: vec4<f32>) -> vec4<f32> {
+    let coverage = blur_color.a;
+    // fwidth makes the softness roughly a number of pixels
+    let aa = fwidth(coverage) * settings.softness;
+    var alpha = step(SOLID_THRESHOLD, coverage);
+    if aa > 0.0 {
+        alpha = smoothstep(SOLID_THRESHOLD - aa, SOLID_THRESHOLD + aa, coverage);
+    }
+    // The outline is premultiplied, recover the original color
+    let color = blur_color.rgb / max(coverage, 0.0001);
+    return vec4(color * alpha, alpha);
+}
 
 @fragment
 fn combine(@location(0) uv: vec2<f32>) -> @location(0) vec4<f32> {
     let stencil_color = textureSample(stencil, stencil_sampler, uv);
     var blur_color = textureSample(blur_texture, stencil_sampler, uv);
+    let coverage = textureSample(stencil_data, stencil_sampler, uv).a;
+    let inner_coverage = textureSample(inner_texture, stencil_sampler, uv).a;
+    let inner_color = stencil_color * inner_coverage;
+    // Derivatives need to be computed before any non uniform branch
+    let solid = solid_outline(blur_color);
+    let inner_solid = solid_outline(inner_color);
+    var interior_lines = vec4(0.0);
+#ifdef INTERIOR_LINES
+    interior_lines = textureSample(interior_lines_texture, stencil_sampler, uv);
+#endif
 
-    // don't render outlines if they overlap
-    if any(stencil_color.xyz > vec3(0.0)) {
+    // outer outlines are never rendered on top of the stencil, inner outlines and interior lines only are
+    if coverage > 0.0 {
+        var inner = vec4(0.0);
+        if settings.inner != 0u {
+            if settings.solid != 0u {
+                inner = inner_solid;
+            } else {
+                inner = inner_color * settings.intensity;
+            }
+        }
+        return interior_lines + inner * (1.0 - interior_lines.a);
+    }
+
+    if settings.outer == 0u {
         return vec4(0.0);
     }
 
+    if settings.solid != 0u {
+        return solid;
+    }
+
     let outline = blur_color - stencil_color;
 
-    // return step(vec4(0.01), outline);
-    return outline * intensity;
+    return outline * settings.intensity;
 }
\ No newline at end of file
@@ -0,0 +1,166 @@
+// Sobel filter over the depth, the normals and the color of the view.
+// With INTERIOR_LINES, only the depth and the normals inside the outlines flagged in the stencil are used.
+// based on https://roystan.net/articles/outline-shader/
+
+struct EdgeDetectSettings {
+    color: vec4<f32>,
+    inverse_projection: mat4x4<f32>,
+    viewport_origin: vec2<f32>,
+    offset: f32,
+    depth_threshold: f32,
+    normal_threshold: f32,
+    color_threshold: f32,
+};
+
+// The view, or the stencil for interior lines
+@group(0) @binding(0)
+var color_texture: texture_2d<f32>;
+#ifdef MULTISAMPLED
+@group(0) @binding(1)
+var depth_prepass_texture: texture_depth_multisampled_2d;
+@group(0) @binding(2)
+var normal_prepass_texture: texture_multisampled_2d<f32>;
+#else
+@group(0) @binding(1)
+var depth_prepass_texture: texture_depth_2d;
+@group(0) @binding(2)
+var normal_prepass_texture: texture_2d<f32>;
+#endif
+@group(0) @binding(3)
+var<uniform> settings: EdgeDetectSettings;
+#ifdef INTERIOR_LINES
+// The blue channel flags the outlines with interior lines, see overlap_rank in stencil_types.wgsl
+@group(0) @binding(4)
+var stencil_rank: texture_2d<f32>;
+#endif
+
+// The horizontal and vertical weights of the 3x3 Sobel kernel at the given offset
+fn sobel_weights(x: i32, y: i32) -> vec2<f32> {
+    return vec2<f32>(f32(x * (2 - abs(y))), f32(y * (2 - abs(x))));
+}
+
+// Keeps the depth of the background finite
+const MIN_W: f32 = 0.000001;
+
+// The neighbours of the pixels at the border of the screen are outside of the textures,
+// the result of loading them isn't defined so the closest texel is used
+fn clamp_coord(coord: vec2<i32>, size: vec2<i32>) -> vec2<i32> {
+    return clamp(coord, vec2(0), size - 1);
+}
+
+#ifdef INTERIOR_LINES
+fn has_interior_lines(coord: vec2<i32>) -> bool {
+    return textureLoad(stencil_rank, clamp_coord(coord, vec2<i32>(textureDimensions(stencil_rank))), 0).z > 0.0;
+}
+#endif
+
+fn sample_offset(frag_coord: vec2<f32>, x: i32, y: i32) -> vec2<f32> {
+#ifdef INTERIOR_LINES
+    // Interior lines are always thin, the size is the one of the outline.
+    // Only the neighbours inside the outlines are compared so their silhouette isn't a line.
+    let offset = vec2<f32>(f32(x), f32(y));
+    if !has_interior_lines(vec2<i32>(frag_coord + offset)) {
+        return vec2<f32>(0.0);
+    }
+    return offset;
+#else
+    return vec2<f32>(f32(x), f32(y)) * settings.offset;
+#endif
+}
+
+// The view and the prepass cover the whole target, the output and the stencil only cover the viewport
+fn target_coord(frag_coord: vec2<f32>, x: i32, y: i32) -> vec2<i32> {
+    return vec2<i32>(frag_coord + settings.viewport_origin + sample_offset(frag_coord, x, y));
+}
+
+// Distance to the camera, the prepass stores a reversed-z depth
+fn linear_depth(coord: vec2<i32>) -> f32 {
+    let depth = textureLoad(depth_prepass_texture, clamp_coord(coord, vec2<i32>(textureDimensions(depth_prepass_texture))), 0);
+    let view_position = settings.inverse_projection * vec4<f32>(0.0, 0.0, depth, 1.0);
+    return -view_position.z / max(view_position.w, MIN_W);
+}
+
+fn normal(coord: vec2<i32>) -> vec3<f32> {
+    let normal = textureLoad(normal_prepass_texture, clamp_coord(coord, vec2<i32>(textureDimensions(normal_prepass_texture))), 0);
+    return normal.xyz * 2.0 - 1.0;
+}
+
+fn luminance(coord: vec2<i32>) -> f32 {
+    let color = textureLoad(color_texture, clamp_coord(coord, vec2<i32>(textureDimensions(color_texture))), 0);
+    return dot(color.rgb, vec3<f32>(0.2126, 0.7152, 0.0722));
+}
+
+// Every gradient is divided by 4 so a step of 1 between two areas gives a gradient of about 1
+fn depth_edge(frag_coord: vec2<f32>) -> f32 {
+    var gradient = vec2<f32>(0.0);
+    for (var x = -1; x <= 1; x++) {
+        for (var y = -1; y <= 1; y++) {
+            let depth = linear_depth(target_coord(frag_coord, x, y));
+            gradient += depth * sobel_weights(x, y);
+        }
+    }
+    // Relative to the depth so the same threshold works close to and far from the camera
+    let depth = linear_depth(target_coord(frag_coord, 0, 0));
+    return length(gradient) * 0.25 / max(depth, MIN_W);
+}
+
+fn normal_edge(frag_coord: vec2<f32>) -> f32 {
+    var gradient_x = vec3<f32>(0.0);
+    var gradient_y = vec3<f32>(0.0);
+    for (var x = -1; x <= 1; x++) {
+        for (var y = -1; y <= 1; y++) {
+            let normal = normal(target_coord(frag_coord, x, y));
+            let weights = sobel_weights(x, y);
+            gradient_x += normal * weights.x;
+            gradient_y += normal * weights.y;
+        }
+    }
+    return sqrt(dot(gradient_x, gradient_x) + dot(gradient_y, gradient_y)) * 0.25;
+}
+
+fn color_edge(frag_coord: vec2<f32>) -> f32 {
+    var gradient = vec2<f32>(0.0);
+    for (var x = -1; x <= 1; x++) {
+        for (var y = -1; y <= 1; y++) {
+            let luminance = luminance(target_coord(frag_coord, x, y));
+            gradient += luminance * sobel_weights(x, y);
+        }
+    }
+    return length(gradient) * 0.25;
+}
+
+// A threshold of 0 ignores the source
+fn is_edge(edge: f32, threshold: f32) -> bool {
+    return threshold > 0.0 && edge > threshold;
+}
+
+@fragment
+fn fragment(@builtin(position) position: vec4<f32>) -> @location(0) vec4<f32> {
+    let frag_coord = floor(position.xy);
+#ifdef INTERIOR_LINES
+    if !has_interior_lines(vec2<i32>(frag_coord)) {
+        return vec4<f32>(0.0);
+    }
+#endif
+    var edge = false;
+#ifdef DEPTH_PREPASS
+    edge = edge || is_edge(depth_edge(frag_coord), settings.depth_threshold);
+#endif
+#ifdef NORMAL_PREPASS
+    edge = edge || is_edge(normal_edge(frag_coord), settings.normal_threshold);
+#endif
+#ifndef INTERIOR_LINES
+    edge = edge || is_edge(color_edge(frag_coord), settings.color_threshold);
+#endif
+
+    if !edge {
+        return vec4<f32>(0.0);
+    }
+#ifdef INTERIOR_LINES
+    // The lines use the color of the outline, it's already premultiplied
+    return textureLoad(color_texture, vec2<i32>(frag_coord), 0);
+#else
+    // The outlines are premultiplied
+    return vec4<f32>(settings.color.rgb * settings.color.a, settings.color.a);
+#endif
+}
@@ -0,0 +1,137 @@
+use bevy::{
+    prelude::*,
+    render::{
+        render_resource::{
+            BindGroupLayout, BindGroupLayoutDescriptor, BindingType, BufferBindingType,
+            RenderPipelineDescriptor, ShaderType, SpecializedRenderPipeline, TextureSampleType,
+            TextureViewDimension,
+        },
+        renderer::RenderDevice,
+    },
+};
+
+use crate::{
+    bind_group_layout_entries,
+    utils::{color_target, RenderPipelineDescriptorBuilder},
+    EdgeDetectSettingsUniform, EDGE_DETECT_SHADER_HANDLE,
+};
+
+#[derive(PartialEq, Eq, Hash, Clone, Copy)]
+pub struct EdgeDetectPipelineKey {
+    /// The view has a depth prepass, a fallback texture is bound otherwise
+    pub depth_prepass: bool,
+    /// The view has a normal prepass, a fallback texture is bound otherwise
+    pub normal_prepass: bool,
+    /// The prepass textures are multisampled
+    pub multisampled: bool,
+    /// Finds the interior lines of the stencil instead of the edges of the view, see [`crate::Outline::interior_lines`]
+    pub interior_lines: bool,
+    /// The view is HDR, the colors are stored in a float texture
+    pub hdr: bool,
+}
+
+#[derive(Resource)]
+pub struct EdgeDetectPipeline {
+    pub layout: BindGroupLayout,
+    pub layout_multisampled: BindGroupLayout,
+}
+
+impl FromWorld for EdgeDetectPipeline {
+    fn from_world(world: &mut World) -> Self {
+        let render_device = world.resource::<RenderDevice>();
+
+        let create_layout = |label, multisampled| {
+            render_device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+                label: Some(label),
+                entries: &bind_group_layout_entries![
+                    // view texture, or stencil texture for interior lines
+                    0 => BindingType::Texture {
+                        sample_type: TextureSampleType::Float { filterable: false },
+                        view_dimension: TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    // depth prepass texture
+                    1 => BindingType::Texture {
+                        sample_type: TextureSampleType::Depth,
+                        view_dimension: TextureViewDimension::D2,
+                        multisampled,
+                    },
+                    // normal prepass texture
+                    2 => BindingType::Texture {
+                        sample_type: TextureSampleType::Float { filterable: !multisampled },
+                        view_dimension: TextureViewDimension::D2,
+                        multisampled,
+                    },
+                    // settings
+                    3 => BindingType::Buffer {
+                        ty: BufferBindingType::Uniform,
+                        has_dynamic_offset: true,
+                        min_binding_size: Some(EdgeDetectSettingsUniform::min_size()),
+                    },
+                    // stencil rank texture, only used by interior lines
+                    4 => BindingType::Texture {
+                        sample_type: TextureSampleType::Float { filterable: false },
+                        view_dimension: TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                ],
+            })
+        };
+
+        EdgeDetectPipeline {
+            layout: create_layout("edge_detect_bind_group_layout", false),
+            layout_multisampled: create_layout("edge_detect_multisampled_bind_group_layout", true),
+        }
+    }
+}
+
+impl EdgeDetectPipeline {
+    /// The layout matching the sample count of the prepass textures
+    pub fn layout(&self, world: &World) -> &BindGroupLayout {
+        match world.resource::<Msaa>().samples() {
+            1 => &self.layout,
+            _ => &self.layout_multisampled,
+        }
+    }
+}
+
+impl SpecializedRenderPipeline for EdgeDetectPipeline {
+    type Key = EdgeDetectPipelineKey;
+
+    fn specialize(&self, key: Self::Key) -> RenderPipelineDescriptor {
+        let mut shader_defs = vec![];
+        if key.depth_prepass {
+            shader_defs.push("DEPTH_PREPASS".into());
+        }
+        if key.normal_prepass {
+            shader_defs.push("NORMAL_PREPASS".into());
+        }
+        if key.interior_lines {
+            shader_defs.push("INTERIOR_LINES".into());
+        }
+        let layout = match key.multisampled {
+            true => {
+                shader_defs.push("MULTISAMPLED".into());
+                self.layout_multisampled.clone()
+            }
+            false => self.layout.clone(),
+        };
+
+        RenderPipelineDescriptorBuilder::fullscreen()
+            .label(
+                match key.interior_lines {
+                    true => "interior_lines_pipeline",
+                    false => "edge_detect_pipeline",
+                }
+                .into(),
+            )
+            .layout(vec![layout])
+            .fragment(
+                EDGE_DETECT_SHADER_HANDLE,
+                "fragment",
+                &[color_target(None, key.hdr)],
+                &shader_defs,
+            )
+            .build()
+    }
+}
@@ -0,0 +1,75 @@
+// Lines inside the stencil where an outline of another OutlineGroup is in front.
+// They are the outline of the group in front drawn over the group behind it, so each group keeps its own outline.
+
+#import bevy_outline::kernel_shape
+
+struct GroupEdgesSettings {
+    size: f32,
+    dims: vec2<f32>,
+    kernel_shape: u32,
+};
+
+// The premultiplied color of the outlines
+@group(0) @binding(0)
+var stencil: texture_2d<f32>;
+// The red channel is the width in pixels and the alpha is the coverage
+@group(0) @binding(1)
+var stencil_data: texture_2d<f32>;
+// The alpha channel is the group, see overlap_rank in stencil_types.wgsl
+@group(0) @binding(2)
+var stencil_rank: texture_2d<f32>;
+@group(0) @binding(3)
+var<uniform> settings: GroupEdgesSettings;
+
+// Must match the documentation of OutlineGroup
+const MAX_EDGE_WIDTH: f32 = 8.0;
+
+// Pixels partially covered by the stencil mix the group with the background, they aren't compared
+fn is_covered(coord: vec2<i32>) -> bool {
+    return textureLoad(stencil_data, coord, 0).a >= 1.0;
+}
+
+// The highest tier wins, then the nearest pixel, then the smallest group when both ranks are equal
+fn is_in_front(rank: vec4<f32>, other: vec4<f32>) -> bool {
+    if rank.x != other.x {
+        return rank.x > other.x;
+    }
+    if rank.y != other.y {
+        return rank.y > other.y;
+    }
+    return rank.w < other.w;
+}
+
+@fragment
+fn fragment(@builtin(position) position: vec4<f32>) -> @location(0) vec4<f32> {
+    let coord = vec2<i32>(position.xy);
+    if !is_covered(coord) {
+        return vec4(0.0);
+    }
+    let own_rank = textureLoad(stencil_rank, coord, 0);
+    let size = vec2<i32>(textureDimensions(stencil_rank));
+    let reach = i32(ceil(min(settings.size, MAX_EDGE_WIDTH)));
+
+    // The edge uses the outline in front of every other one reaching the pixel
+    var color = vec4(0.0);
+    var best_rank = own_rank;
+    for (var y = -reach; y <= reach; y++) {
+        for (var x = -reach; x <= reach; x++) {
+            let other_coord = coord + vec2(x, y);
+            if any(other_coord < vec2(0)) || any(other_coord >= size) || !is_covered(other_coord) {
+                continue;
+            }
+            let rank = textureLoad(stencil_rank, other_coord, 0);
+            if rank.w == own_rank.w || !is_in_front(rank, best_rank) {
+                continue;
+            }
+            let width = min(textureLoad(stencil_data, other_coord, 0).r, MAX_EDGE_WIDTH);
+            if kernel_distance(vec2<f32>(vec2(x, y)), settings.kernel_shape) > width {
+                continue;
+            }
+            color = textureLoad(stencil, other_coord, 0);
+            best_rank = rank;
+        }
+    }
+    return color;
+}
@@ -0,0 +1,303 @@
+use bevy::{
+    prelude::*,
+    render::{
+        render_resource::{
+            ColorTargetState, ColorWrites, DynamicUniformBuffer, ShaderType, TextureFormat,
+        },
+        renderer::{RenderDevice, RenderQueue},
+    },
+};
+
+#[cfg(test)]
+use crate::OutlineKernelShape;
+
+/// Stores the position of the closest seed, 16 bits floats aren't precise enough for large screens
+pub const JFA_TEXTURE_FORMAT: TextureFormat = TextureFormat::Rg32Float;
+
+/// Maximum number of flood passes, enough for outlines up to 2^15 pixels wide
+pub const MAX_JFA_PASSES: usize = 16;
+
+pub fn jfa_color_target() -> ColorTargetState {
+    ColorTargetState {
+        format: JFA_TEXTURE_FORMAT,
+        blend: None,
+        write_mask: ColorWrites::ALL,
+    }
+}
+
+#[derive(ShaderType, Clone)]
+pub struct JfaFloodUniform {
+    step: f32,
+}
+
+/// Uniforms for every possible flood step. Each pass binds the offset of the step it needs.
+#[derive(Resource, Default)]
+pub struct JfaFloodUniforms {
+    pub buffer: DynamicUniformBuffer<JfaFloodUniform>,
+    offsets: Vec<u32>,
+}
+
+impl JfaFloodUniforms {
+    /// Dynamic offset of the uniform for the given step
+    pub fn offset(&self, step: u32) -> u32 {
+        self.offsets[step.trailing_zeros() as usize]
+    }
+}
+
+/// The steps never change so the buffer only needs to be written once
+pub fn prepare_jfa_flood_uniforms(
+    mut uniforms: ResMut<JfaFloodUniforms>,
+    render_device: Res<RenderDevice>,
+    render_queue: Res<RenderQueue>,
+) {
+    if !uniforms.offsets.is_empty() {
+        return;
+    }
+
+    for i in 0..MAX_JFA_PASSES {
+        let offset = uniforms.buffer.push(JfaFloodUniform {
+            step: (1u32 << i) as f32,
+        });
+        uniforms.offsets.push(offset);
+    }
+    uniforms.buffer.write_buffer(&render_device, &render_queue);
+}
+
+/// The step of each flood pass required to find every seed at most `size` pixels away.
+///
+/// The steps start at the smallest power of two covering the size and are halved until 1.
+/// An extra pass of step 1 is added at the end (JFA+1) to fix most of the errors
+/// the regular algorithm makes when seeds are sparse.
+pub fn jfa_steps(size: f32) -> impl Iterator<Item = u32> {
+    let passes = if size < 1.0 {
+        0
+    } else {
+        let max_step = 1 << (MAX_JFA_PASSES - 1);
+        let first_step = (size.ceil() as u32).min(max_step).next_power_of_two();
+        first_step.trailing_zeros() as usize + 1
+    };
+    (0..passes)
+        .rev()
+        .chain((passes > 0).then_some(0))
+        .map(|i| 1 << i)
+}
+
+/// CPU reference implementation of the flood done in `jfa.wgsl`.
+///
+/// `seeds` contains the width of the outline of every pixel covered by the stencil.
+/// Returns the position of the closest seed for every pixel, seeds are expressed in pixel
+/// centers like `@builtin(position)`. The distances are measured with the `kernel_shape`.
+#[cfg(test)]
+pub fn jump_flood(
+    width: usize,
+    height: usize,
+    seeds: &[Option<f32>],
+    size: f32,
+    kernel_shape: OutlineKernelShape,
+) -> Vec<Option<Vec2>> {
+    assert_eq!(seeds.len(), width * height);
+
+    let pixel_center = |x: usize, y: usize| Vec2::new(x as f32 + 0.5, y as f32 + 0.5);
+    let seed_width = |seed: Vec2| {
+        let index = seed.y as usize * width + seed.x as usize;
+        seeds[index].unwrap_or_default().min(size)
+    };
+
+    // seed pass
+    let mut jfa: Vec<Option<Vec2>> = (0..width * height)
+        .map(|i| seeds[i].map(|_| pixel_center(i % width, i / width)))
+        .collect();
+
+    // flood passes
+    for step in jfa_steps(size) {
+        let step = step as i32;
+        let mut next = vec![None; width * height];
+        for y in 0..height {
+            for x in 0..width {
+                let position = pixel_center(x, y);
+                let mut best_seed = None;
+                let mut best_distance = f32::INFINITY;
+                for offset_y in -1..=1 {
+                    for offset_x in -1..=1 {
+                        let sample_x = x as i32 + offset_x * step;
+                        let sample_y = y as i32 + offset_y * step;
+                        if sample_x < 0
+                            || sample_y < 0
+                            || sample_x >= width as i32
+                            || sample_y >= height as i32
+                        {
+                            continue;
+                        }
+                        let Some(seed) = jfa[sample_y as usize * width + sample_x as usize] else {
+                            continue;
+                        };
+                        let distance = kernel_shape.distance(position - seed) - seed_width(seed);
+                        if distance < best_distance {
+                            best_seed = Some(seed);
+                            best_distance = distance;
+                        }
+                    }
+                }
+                next[y * width + x] = best_seed;
+            }
+        }
+        jfa = next;
+    }
+
+    jfa
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Distance to the edge of the closest outline found by checking every seed
+    fn brute_force(
+        width: usize,
+        height: usize,
+        seeds: &[Option<f32>],
+        size: f32,
+        kernel_shape: OutlineKernelShape,
+    ) -> Vec<f32> {
+        let mut out = Vec::with_capacity(width * height);
+        for y in 0..height {
+            for x in 0..width {
+                let position = Vec2::new(x as f32, y as f32);
+                out.push(
+                    (0..width * height)
+                        .filter_map(|i| {
+                            let seed = Vec2::new((i % width) as f32, (i / width) as f32);
+                            seeds[i].map(|w| kernel_shape.distance(position - seed) - w.min(size))
+                        })
+                        .fold(f32::INFINITY, f32::min),
+                );
+            }
+        }
+        out
+    }
+
+    fn assert_matches_brute_force(
+        width: usize,
+        height: usize,
+        seeds: &[Option<f32>],
+        size: f32,
+        kernel_shape: OutlineKernelShape,
+    ) {
+        let jfa = jump_flood(width, height, seeds, size, kernel_shape);
+        let expected = brute_force(width, height, seeds, size, kernel_shape);
+        for (i, (jfa, expected)) in jfa.iter().zip(expected).enumerate() {
+            // Only the pixels covered by an outline matter
+            if expected > 0.0 {
+                continue;
+            }
+            let seed = jfa.expect("pixel in range should have a seed");
+            let seed_width = seeds[seed.y as usize * width + seed.x as usize].unwrap();
+            let position = Vec2::new((i % width) as f32 + 0.5, (i / width) as f32 + 0.5);
+            let distance = kernel_shape.distance(position - seed) - seed_width.min(size);
+            assert!(
+                (distance - expected).abs() < 1e-4,
+                "pixel {i}: jfa distance {distance} != {expected}"
+            );
+        }
+    }
+
+    #[test]
+    fn steps() {
+        assert_eq!(jfa_steps(0.0).collect::<Vec<_>>(), Vec::<u32>::new());
+        assert_eq!(jfa_steps(1.0).collect::<Vec<_>>(), vec![1, 1]);
+        assert_eq!(jfa_steps(3.0).collect::<Vec<_>>(), vec![4, 2, 1, 1]);
+        assert_eq!(
+            jfa_steps(64.0).collect::<Vec<_>>(),
+            vec![64, 32, 16, 8, 4, 2, 1, 1]
+        );
+        assert_eq!(jfa_steps(f32::MAX).count(), MAX_JFA_PASSES + 1);
+    }
+
+    #[test]
+    fn single_seed() {
+        let (width, height) = (32, 24);
+        let mut seeds = vec![None; width * height];
+        seeds[10 * width + 7] = Some(16.0);
+        assert_matches_brute_force(width, height, &seeds, 16.0, OutlineKernelShape::Circle);
+    }
+
+    #[test]
+    fn rectangle() {
+        let (width, height) = (40, 40);
+        let mut seeds = vec![None; width * height];
+        for y in 15..25 {
+            for x in 12..20 {
+                seeds[y * width + x] = Some(12.0);
+            }
+        }
+        assert_matches_brute_force(width, height, &seeds, 12.0, OutlineKernelShape::Circle);
+    }
+
+    #[test]
+    fn multiple_seeds() {
+        let (width, height) = (48, 32);
+        let mut seeds = vec![None; width * height];
+        seeds[4 * width + 4] = Some(20.0);
+        seeds[20 * width + 30] = Some(20.0);
+        seeds[28 * width + 10] = Some(20.0);
+        assert_matches_brute_force(width, height, &seeds, 20.0, OutlineKernelShape::Circle);
+    }
+
+    #[test]
+    fn per_seed_width() {
+        let (width, height) = (48, 32);
+        let mut seeds = vec![None; width * height];
+        // A thin outline right next to a wide one, the wide one must still be found
+        seeds[16 * width + 20] = Some(2.0);
+        seeds[16 * width + 24] = Some(10.0);
+        seeds[4 * width + 40] = Some(6.0);
+        assert_matches_brute_force(width, height, &seeds, 12.0, OutlineKernelShape::Circle);
+    }
+
+    #[test]
+    fn width_clamped_to_size() {
+        let (width, height) = (32, 32);
+        let mut seeds = vec![None; width * height];
+        seeds[16 * width + 16] = Some(100.0);
+        let jfa = jump_flood(width, height, &seeds, 4.0, OutlineKernelShape::Circle);
+        let covered = jfa
+            .iter()
+            .enumerate()
+            .filter(|(i, seed)| {
+                let position = Vec2::new((i % width) as f32 + 0.5, (i / width) as f32 + 0.5);
+                matches!(seed, Some(seed) if seed.distance(position) <= 4.0)
+            })
+            .count();
+        // every pixel in a radius of 4 around the seed
+        assert_eq!(covered, 49);
+    }
+
+    #[test]
+    fn no_seeds() {
+        let jfa = jump_flood(8, 8, &[None; 64], 8.0, OutlineKernelShape::Circle);
+        assert!(jfa.iter().all(Option::is_none));
+    }
+
+    #[test]
+    fn kernel_shapes() {
+        let (width, height) = (48, 32);
+        let mut seeds = vec![None; width * height];
+        for y in 10..14 {
+            for x in 8..20 {
+                seeds[y * width + x] = Some(8.0);
+            }
+        }
+        seeds[24 * width + 36] = Some(6.0);
+        for kernel_shape in [OutlineKernelShape::Square, OutlineKernelShape::Diamond] {
+            assert_matches_brute_force(width, height, &seeds, 8.0, kernel_shape);
+        }
+    }
+
+    #[test]
+    fn kernel_shape_distance() {
+        let offset = Vec2::new(-3.0, 4.0);
+        assert_eq!(OutlineKernelShape::Square.distance(offset), 4.0);
+        assert_eq!(OutlineKernelShape::Circle.distance(offset), 5.0);
+        assert_eq!(OutlineKernelShape::Diamond.distance(offset), 7.0);
+    }
+}
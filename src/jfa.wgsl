@@ -0,0 +1,145 @@
+// Jump Flood Algorithm
+// based on https://bgolus.medium.com/the-quest-for-very-wide-outlines-ba82ed442cd9
+
+#import bevy_render::globals
+#import bevy_outline::kernel_shape
+
+struct JfaSettings {
+    size: f32,
+    dims: vec2<f32>,
+    kernel_shape: u32,
+};
+
+struct JfaFloodSettings {
+    step: f32,
+};
+
+@group(0) @binding(0)
+var jfa_texture: texture_2d<f32>;
+@group(0) @binding(1)
+var stencil: texture_2d<f32>;
+@group(0) @binding(2)
+var stencil_sampler: sampler;
+@group(0) @binding(3)
+var<uniform> settings: JfaSettings;
+@group(0) @binding(4)
+var<uniform> flood_settings: JfaFloodSettings;
+@group(0) @binding(5)
+var stencil_data: texture_2d<f32>;
+@group(0) @binding(6)
+var<uniform> globals: Globals;
+// The rank of each pixel of the stencil, see overlap_rank in stencil_types.wgsl
+@group(0) @binding(7)
+var stencil_rank: texture_2d<f32>;
+
+// Used to mark pixels that don't have a seed yet
+const NO_SEED: vec2<f32> = vec2<f32>(-1.0, -1.0);
+
+// Distance between a pixel and a seed, it depends on the shape of the kernel
+fn seed_distance_to(seed: vec2<f32>, position: vec2<f32>) -> f32 {
+    return kernel_distance(position - seed, settings.kernel_shape);
+}
+
+// Width of the outline of the given seed, it can't be larger than the size of the view
+fn seed_width(seed: vec2<f32>) -> f32 {
+    return min(textureLoad(stencil_data, vec2<i32>(seed), 0).r, settings.size);
+}
+
+// Dashes scrolling along the outline, the length and speed are stored in the stencil data of the seed.
+// The direction to the seed is perpendicular to the edge, this gives the position of the seed along the edge.
+fn dash(seed: vec2<f32>, position: vec2<f32>) -> f32 {
+    let data = textureLoad(stencil_data, vec2<i32>(seed), 0);
+    let dash_length = data.g;
+    let direction = position - seed;
+    // The seeds themselves are covered by the stencil
+    if dash_length <= 0.0 || all(direction == vec2(0.0)) {
+        return 1.0;
+    }
+    let tangent = normalize(vec2(-direction.y, direction.x));
+    let along_edge = dot(seed, tangent) + globals.time * data.b;
+    return step(fract(along_edge / (2.0 * dash_length)), 0.5);
+}
+
+// Every pixel covered by the stencil becomes a seed pointing to itself.
+// Inner outlines use every pixel outside the stencil instead.
+@fragment
+fn seed(@builtin(position) position: vec4<f32>, @location(0) uv: vec2<f32>) -> @location(0) vec4<f32> {
+    // The seed pass binds the stencil data, its alpha is the coverage
+    let coverage = textureSample(stencil, stencil_sampler, uv).a;
+    var is_seed = coverage > 0.0;
+#ifdef INNER
+    is_seed = !is_seed;
+#endif
+    if is_seed {
+        return vec4(position.xy, 0.0, 0.0);
+    }
+    return vec4(NO_SEED, 0.0, 0.0);
+}
+
+// Where the outlines of both seeds reach the pixel, the seed with the highest rank is better.
+// Otherwise the closest seed is better.
+fn is_better_seed(seed: vec2<f32>, seed_distance: f32, best_seed: vec2<f32>, best_distance: f32) -> bool {
+    // A seed reaches the pixel while the coverage of the outline pass is positive
+    if seed_distance < 1.0 && best_distance < 1.0 {
+        let rank = textureLoad(stencil_rank, vec2<i32>(seed), 0).xy;
+        let best_rank = textureLoad(stencil_rank, vec2<i32>(best_seed), 0).xy;
+        if rank.x != best_rank.x {
+            return rank.x > best_rank.x;
+        }
+        if rank.y != best_rank.y {
+            return rank.y > best_rank.y;
+        }
+    }
+    return seed_distance < best_distance;
+}
+
+// Looks at the 8 neighbours `step` pixels away and keeps the best seed.
+// The distance is offset by the width of the seed, this way a wide outline
+// is still visible next to a closer seed with a thinner outline.
+// Seeds outside the stencil have no width nor rank, so inner outlines use the regular distance.
+@fragment
+fn flood(@builtin(position) position: vec4<f32>) -> @location(0) vec4<f32> {
+    let dims = vec2<i32>(textureDimensions(jfa_texture));
+    let coord = vec2<i32>(position.xy);
+    let step = i32(flood_settings.step);
+
+    var best_seed = NO_SEED;
+    var best_distance = 0.0;
+    for (var y = -1; y <= 1; y++) {
+        for (var x = -1; x <= 1; x++) {
+            let sample_coord = coord + vec2(x, y) * step;
+            if any(sample_coord < vec2(0)) || any(sample_coord >= dims) {
+                continue;
+            }
+            let seed = textureLoad(jfa_texture, sample_coord, 0).xy;
+            if seed.x < 0.0 {
+                continue;
+            }
+            let seed_distance = seed_distance_to(seed, position.xy) - seed_width(seed);
+            if best_seed.x < 0.0 || is_better_seed(seed, seed_distance, best_seed, best_distance) {
+                best_seed = seed;
+                best_distance = seed_distance;
+            }
+        }
+    }
+    return vec4(best_seed, 0.0, 0.0);
+}
+
+// Converts the distance to the closest seed into an outline of the seed's color
+@fragment
+fn outline(@builtin(position) position: vec4<f32>) -> @location(0) vec4<f32> {
+    let seed = textureLoad(jfa_texture, vec2<i32>(position.xy), 0).xy;
+    // Sample before branching, textureSample needs uniform control flow
+    let seed_color = textureSample(stencil, stencil_sampler, seed * settings.dims);
+    if seed.x < 0.0 {
+        return vec4(0.0);
+    }
+#ifdef INNER
+    // The width of the pixel inside the stencil is used, the color is added back in the combine pass
+    let coverage = saturate(seed_width(position.xy) + 1.0 - seed_distance_to(seed, position.xy));
+    return vec4(coverage);
+#else
+    let coverage = saturate(seed_width(seed) + 1.0 - seed_distance_to(seed, position.xy));
+    return seed_color * coverage * dash(seed, position.xy);
+#endif
+}
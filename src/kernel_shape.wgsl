@@ -0,0 +1,18 @@
+#define_import_path bevy_outline::kernel_shape
+
+// Must match OutlineKernelShape::as_u32
+const KERNEL_SQUARE: u32 = 0u;
+const KERNEL_CIRCLE: u32 = 1u;
+const KERNEL_DIAMOND: u32 = 2u;
+
+// Distance used to decide if an offset is covered by an outline, it must match OutlineKernelShape::distance
+fn kernel_distance(offset: vec2<f32>, shape: u32) -> f32 {
+    let offset = abs(offset);
+    if shape == KERNEL_SQUARE {
+        return max(offset.x, offset.y);
+    }
+    if shape == KERNEL_DIAMOND {
+        return offset.x + offset.y;
+    }
+    return length(offset);
+}
@@ -2,36 +2,73 @@
 #![allow(clippy::too_many_arguments)]
 
 mod blur_pipeline;
+mod edge_detect_pipeline;
+mod jfa;
+mod max_filter_pipeline;
 pub mod node;
+#[cfg(feature = "picking")]
+mod selection;
 mod stencil_phase;
+mod stencil_phase_2d;
+mod transition;
+mod tree;
 mod utils;
+mod viewport;
+mod width;
 
 use bevy::{
     asset::load_internal_asset,
-    core_pipeline::core_3d,
+    core_pipeline::{
+        core_2d, core_3d,
+        prepass::{DepthPrepass, NormalPrepass},
+    },
     prelude::*,
     reflect::TypeUuid,
     render::{
-        camera::ExtractedCamera,
         extract_component::{ExtractComponent, ExtractComponentPlugin, UniformComponentPlugin},
+        globals::GlobalsUniform,
         render_graph::RenderGraph,
         render_resource::{
             AddressMode, BindGroupLayout, BindGroupLayoutDescriptor, BindingType, BlendState,
             BufferBindingType, CachedRenderPipelineId, Extent3d, FilterMode, LoadOp, Operations,
-            PipelineCache, RenderPassColorAttachment, Sampler, SamplerBindingType,
-            SamplerDescriptor, ShaderType, SpecializedRenderPipelines, TextureDescriptor,
-            TextureDimension, TextureFormat, TextureSampleType, TextureUsages,
-            TextureViewDimension,
+            PipelineCache, RenderPassColorAttachment, RenderPassDepthStencilAttachment,
+            RenderPipelineDescriptor, Sampler, SamplerBindingType, SamplerDescriptor, ShaderType,
+            SpecializedRenderPipelines, TextureDescriptor, TextureDimension, TextureSampleType,
+            TextureUsages, TextureViewDimension,
         },
         renderer::RenderDevice,
-        texture::{BevyDefault, CachedTexture, TextureCache},
+        texture::{CachedTexture, FallbackImagesDepth, FallbackImagesMsaa, TextureCache},
+        view::ExtractedView,
         Extract, RenderApp, RenderSet,
     },
+    ui::draw_ui_graph,
+    utils::HashMap,
 };
 use blur_pipeline::{BlurDirection, BlurPipeline, BlurPipelineKey, BlurType};
-use utils::{color_target, RenderPipelineDescriptorBuilder};
+use edge_detect_pipeline::{EdgeDetectPipeline, EdgeDetectPipelineKey};
+use jfa::{
+    jfa_color_target, prepare_jfa_flood_uniforms, JfaFloodUniform, JfaFloodUniforms,
+    JFA_TEXTURE_FORMAT,
+};
+use max_filter_pipeline::{MaxFilterPipeline, MaxFilterPipelineKey};
+use transition::{animate_outline_transitions, displayed_outline, OutlineTransitionState};
+use tree::propagate_outline_trees;
+use utils::{color_target, color_texture_format, RenderPipelineDescriptorBuilder};
+use viewport::OutlineViewport;
+use width::max_pixel_width;
+
+use crate::{
+    blur_pipeline::BlurUniform,
+    node::{OutlineHullNode, OutlineNode},
+    stencil_phase::{
+        GroupEdgesLayers, InteriorLinesLayers, MeshStencilPlugin, StencilLayerUniform,
+        STENCIL_DATA_TEXTURE_FORMAT, STENCIL_DEPTH_FORMAT, STENCIL_RANK_TEXTURE_FORMAT,
+    },
+    stencil_phase_2d::Stencil2dPlugin,
+};
 
-use crate::{blur_pipeline::BlurUniform, node::OutlineNode, stencil_phase::MeshStencilPlugin};
+#[cfg(feature = "picking")]
+pub use selection::{OutlineSelectionPlugin, OutlineSelectionStyles};
 
 const BLUR_SHADER_HANDLE: HandleUntyped =
     HandleUntyped::weak_from_u64(Shader::TYPE_UUID, 14687827633551304793);
@@ -42,9 +79,205 @@ const COMBINE_SHADER_HANDLE: HandleUntyped =
 const MAX_FILTER_SHADER_HANDLE: HandleUntyped =
     HandleUntyped::weak_from_u64(Shader::TYPE_UUID, 3759434788503552836);
 
-#[derive(Component, Clone, Copy, Default, ExtractComponent)]
+const JFA_SHADER_HANDLE: HandleUntyped =
+    HandleUntyped::weak_from_u64(Shader::TYPE_UUID, 9312783514069485105);
+
+const EDGE_DETECT_SHADER_HANDLE: HandleUntyped =
+    HandleUntyped::weak_from_u64(Shader::TYPE_UUID, 8501048374204310783);
+
+const KERNEL_SHAPE_SHADER_HANDLE: HandleUntyped =
+    HandleUntyped::weak_from_u64(Shader::TYPE_UUID, 6188260212840937453);
+
+const GROUP_EDGES_SHADER_HANDLE: HandleUntyped =
+    HandleUntyped::weak_from_u64(Shader::TYPE_UUID, 11296738417930625181);
+
+/// Outlines a mesh, a 2d mesh, a [`Sprite`] or a [`TextureAtlasSprite`].
+///
+/// The transparent pixels of sprites are not outlined.
+/// Add an [`OutlineTree`] next to it to also outline the descendants of the entity,
+/// and an [`OutlineTransition`] to animate the changes of the outline.
+#[derive(Component, Clone, Copy, Debug, Default, PartialEq)]
 pub struct Outline {
+    /// Any color can be used, including black.
+    /// The alpha makes a glowing outline more transparent, solid outlines stay opaque.
+    /// On a camera with `hdr` enabled, values above 1.0 are kept and can feed the bloom.
     pub color: Color,
+    /// The width of the outline of this entity, in the [`OutlineWidthUnit`] of the view.
+    /// Uses the size of the [`OutlineSettings`] of the view when `None`, which is also the maximum width.
+    /// Only supported by [`OutlineType::MaxFilter`], [`OutlineType::Jfa`] and [`OutlineType::InvertedHull`].
+    pub width: Option<f32>,
+    /// How the outline behaves when the entity is hidden behind other meshes
+    pub occlusion: OutlineOcclusion,
+    /// The layer of the camera used to draw this outline, see [`OutlineLayers`]
+    pub layer: OutlineLayer,
+    /// Only outline the opaque texels of the base color texture of the entity's [`StandardMaterial`].
+    ///
+    /// Uses the cutoff of [`AlphaMode::Mask`], or 0.5 for any other alpha mode.
+    /// Useful for cut-out textures like foliage or fences. Sprites always use their alpha.
+    pub alpha_mask: bool,
+    /// Which color is shown where this outline touches the outline of another entity
+    pub overlap: OutlineOverlap,
+    /// Also draws lines inside the entity where its depth or its normals change abruptly, like the seams of a box.
+    ///
+    /// The lines use the color of the outline and the depth and normal thresholds of the [`OutlineEdgeDetection`]
+    /// of the layer. They require the [`DepthPrepass`] or [`NormalPrepass`] components on a 3d camera.
+    /// Not supported by [`OutlineType::InvertedHull`].
+    pub interior_lines: bool,
+}
+
+// The outlines fading out with an OutlineTransition are extracted too, they don't have an Outline anymore.
+// The outline drawn during a transition replaces the one of the entity.
+impl ExtractComponent for Outline {
+    type Query = (
+        Option<&'static Outline>,
+        Option<&'static OutlineTransitionState>,
+    );
+    type Filter = Or<(With<Outline>, With<OutlineTransitionState>)>;
+    type Out = Outline;
+
+    fn extract_component(
+        (outline, state): (Option<&Outline>, Option<&OutlineTransitionState>),
+    ) -> Option<Outline> {
+        displayed_outline(outline, state).map(|displayed| displayed.outline)
+    }
+}
+
+/// Animates the [`Outline`] of an entity, the animation is evaluated in the shaders.
+///
+/// Every effect is disabled when its value is 0.
+#[derive(Component, Clone, Copy, Debug, Default, PartialEq)]
+pub struct OutlineAnimation {
+    /// How much the outline fades out during a pulse, between 0 and 1.
+    /// Only visible with [`OutlineStyle::Glow`].
+    pub pulse_amplitude: f32,
+    /// Number of pulses per second
+    pub pulse_frequency: f32,
+    /// Length of the dashes in pixels, the gaps have the same length.
+    /// Only supported by [`OutlineType::Jfa`].
+    pub dash_length: f32,
+    /// Speed at which the dashes scroll along the outline in pixels per second
+    pub dash_speed: f32,
+    /// Number of full cycles through every hue per second
+    pub hue_speed: f32,
+}
+
+/// Animates the [`Outline`] of this entity when it's added, removed or changed instead of switching instantly.
+///
+/// The outline fades in and out, shrinking and growing with [`OutlineType::MaxFilter`], [`OutlineType::Jfa`]
+/// and [`OutlineType::InvertedHull`]. The solid outlines of the blurs fade in and out at once.
+/// The color is interpolated, the other fields switch at the start. The width is only interpolated when both
+/// widths are set, a change from or to `None` isn't animated since the size of the layer depends on the camera.
+/// It must stay on the entity while the outline fades out.
+#[derive(Component, Clone, Copy, Debug, PartialEq)]
+pub struct OutlineTransition {
+    /// Seconds taken by the transition
+    pub duration: f32,
+    pub easing: OutlineEasing,
+}
+
+impl Default for OutlineTransition {
+    fn default() -> Self {
+        Self {
+            duration: 0.2,
+            easing: OutlineEasing::default(),
+        }
+    }
+}
+
+/// Controls the pace of an [`OutlineTransition`]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OutlineEasing {
+    Linear,
+    /// Starts slowly
+    EaseIn,
+    /// Ends slowly
+    EaseOut,
+    /// Starts and ends slowly
+    #[default]
+    EaseInOut,
+}
+
+impl OutlineEasing {
+    /// Maps the progress of the transition, between 0 and 1, to its eased progress
+    pub fn ease(self, t: f32) -> f32 {
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            OutlineEasing::Linear => t,
+            OutlineEasing::EaseIn => t * t,
+            OutlineEasing::EaseOut => t * (2.0 - t),
+            OutlineEasing::EaseInOut => t * t * (3.0 - 2.0 * t),
+        }
+    }
+}
+
+/// Gives separate outlines to entities of the same [`OutlineLayer`] that overlap on the screen.
+///
+/// The outlines of entities sharing a group merge into one silhouette, while a line is drawn inside the
+/// silhouette where an entity of another group is in front, with the color and width of its outline.
+/// Entities without this component are in group 0. Any ids can be used, but a layer can only tell 2048 groups apart,
+/// the groups with the largest ids are outlined together beyond that and a warning is logged.
+/// The lines are at most 8 pixels wide and aren't dashed.
+/// Not supported by [`OutlineType::InvertedHull`] and [`OutlineType::EdgeDetect`].
+#[derive(Component, Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct OutlineGroup(pub u32);
+
+/// Outlines every mesh and sprite below this entity with its [`Outline`], [`OutlineAnimation`] and [`OutlineTransition`].
+///
+/// Useful for scenes, like the root of a [`SceneBundle`], whose meshes are spawned later.
+/// The descendants are kept in sync as they are spawned and despawned, the ones with their own [`Outline`] keep it.
+/// Use [`OutlineOptOut`] to skip a part of the hierarchy.
+#[derive(Component, Clone, Copy, Debug, Default)]
+pub struct OutlineTree;
+
+/// Keeps the [`OutlineTree`] of an ancestor from outlining this entity and its descendants
+#[derive(Component, Clone, Copy, Debug, Default)]
+pub struct OutlineOptOut;
+
+/// Identifies a set of outlines drawn with the same [`OutlineSettings`]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct OutlineLayer(pub u8);
+
+/// The settings of every [`OutlineLayer`] of a camera.
+///
+/// Each layer draws its outlines independently, for example a thin solid outline for selected
+/// entities and a wide glow for hovered ones. Layers without any visible outline are skipped.
+/// The [`OutlineSettings`] of the camera, if any, are used for the default layer unless it is in the map.
+#[derive(Component, Clone, Debug, Default)]
+pub struct OutlineLayers(pub HashMap<OutlineLayer, OutlineSettings>);
+
+/// Controls the outline of the parts of a mesh that are hidden behind other meshes.
+///
+/// Anything other than [`OutlineOcclusion::AlwaysVisible`] requires the
+/// [`DepthPrepass`](bevy::core_pipeline::prepass::DepthPrepass) component on the camera.
+/// Without it, nothing is ever considered occluded. This is always ignored by 2d cameras.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum OutlineOcclusion {
+    /// The outline is drawn through other meshes
+    #[default]
+    AlwaysVisible,
+    /// Only the visible parts of the mesh are outlined
+    VisibleOnly,
+    /// The hidden parts of the mesh are outlined with a different color
+    XRay { color: Color },
+}
+
+/// Decides which outline is visible where the outlines of several entities of the same layer overlap.
+///
+/// Outlines with a higher priority are drawn above the nearest outlines,
+/// which are drawn above the blended outlines.
+/// [`OutlineType::BoxBlur`] and [`OutlineType::GaussianBlur`] always blend the glow around the entities,
+/// only the entities themselves follow this order.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OutlineOverlap {
+    /// The outline of the entity closest to the camera is visible
+    #[default]
+    Nearest,
+    /// The outline with the highest priority is visible, clamped between -1000 and 1000.
+    /// When the priorities are equal, the closest entity is visible.
+    Priority(i32),
+    /// The colors of the overlapping blended outlines are mixed.
+    /// [`OutlineType::Jfa`] can't mix them, the nearest outline is visible.
+    Blend,
 }
 
 pub mod graph {
@@ -54,10 +287,31 @@ pub mod graph {
 
     pub mod node {
         pub const OUTLINE_PASS: &str = "outline_pass";
+        pub const OUTLINE_HULL_PASS: &str = "outline_hull_pass";
     }
 }
 
+/// Where the outlines are drawn in the render graphs of the 2d and 3d cameras.
+///
+/// Insert it as a resource before adding the [`OutlinePlugin`], the default placement is used without it.
+/// The graphs are built with the plugin, a warning is logged when the resource changes afterwards.
+/// [`OutlineType::InvertedHull`] outlines are part of the scene, they are always drawn right after the main pass.
+#[derive(Resource, Clone, Copy, Debug, PartialEq, Eq, Hash, Default)]
+pub enum OutlinePassPlacement {
+    /// After the main pass, before bloom and tonemapping.
+    /// The outlines are tonemapped like the rest of the scene and bright colors of HDR cameras bloom.
+    #[default]
+    BeforeTonemapping,
+    /// After tonemapping, before FXAA and the UI. The outline colors are written as they are.
+    AfterTonemapping,
+    /// After the UI, the outlines are drawn on top of it
+    AfterUi,
+    /// The nodes aren't added to any graph, use [`add_outline_node`] to insert them into a custom graph
+    Manual,
+}
+
 pub struct OutlinePlugin;
+
 impl Plugin for OutlinePlugin {
     fn build(&self, app: &mut App) {
         load_internal_asset!(app, BLUR_SHADER_HANDLE, "blur.wgsl", Shader::from_wgsl);
@@ -73,13 +327,56 @@ impl Plugin for OutlinePlugin {
             "max_filter.wgsl",
             Shader::from_wgsl
         );
+        load_internal_asset!(app, JFA_SHADER_HANDLE, "jfa.wgsl", Shader::from_wgsl);
+        load_internal_asset!(
+            app,
+            EDGE_DETECT_SHADER_HANDLE,
+            "edge_detect.wgsl",
+            Shader::from_wgsl
+        );
+        load_internal_asset!(
+            app,
+            KERNEL_SHAPE_SHADER_HANDLE,
+            "kernel_shape.wgsl",
+            Shader::from_wgsl
+        );
+        load_internal_asset!(
+            app,
+            GROUP_EDGES_SHADER_HANDLE,
+            "group_edges.wgsl",
+            Shader::from_wgsl
+        );
 
         app.add_plugin(ExtractComponentPlugin::<Outline>::default())
-            .add_plugin(ExtractComponentPlugin::<OutlineSettings>::default())
             .add_plugin(UniformComponentPlugin::<BlurUniform>::default())
             .add_plugin(UniformComponentPlugin::<CombineSettingsUniform>::default())
             .add_plugin(UniformComponentPlugin::<MaxFilterSettingsUniform>::default())
-            .add_plugin(MeshStencilPlugin);
+            .add_plugin(UniformComponentPlugin::<EdgeDetectSettingsUniform>::default())
+            .add_plugin(MeshStencilPlugin)
+            .add_plugin(Stencil2dPlugin)
+            // The copies of a tree are inserted in time to start their transitions in the same frame as the tree
+            .add_systems(
+                (
+                    propagate_outline_trees,
+                    apply_system_buffers,
+                    animate_outline_transitions,
+                )
+                    .chain()
+                    .in_base_set(CoreSet::PostUpdate),
+            );
+
+        let placement = *app
+            .init_resource::<OutlinePassPlacement>()
+            .world
+            .resource::<OutlinePassPlacement>();
+        app.add_system(
+            (move |current: Res<OutlinePassPlacement>| {
+                if *current != placement {
+                    warn!("The OutlinePassPlacement changed to {current:?} after the OutlinePlugin was added, the outlines stay {placement:?}. Insert it before adding the plugin");
+                }
+            })
+            .run_if(resource_changed::<OutlinePassPlacement>()),
+        );
 
         let Ok(render_app) = app.get_sub_app_mut(RenderApp) else {
             return;
@@ -88,28 +385,99 @@ impl Plugin for OutlinePlugin {
         render_app
             .init_resource::<BlurPipeline>()
             .init_resource::<SpecializedRenderPipelines<BlurPipeline>>()
+            .init_resource::<MaxFilterPipeline>()
+            .init_resource::<SpecializedRenderPipelines<MaxFilterPipeline>>()
+            .init_resource::<EdgeDetectPipeline>()
+            .init_resource::<SpecializedRenderPipelines<EdgeDetectPipeline>>()
             .init_resource::<OutlineMeta>()
+            .init_resource::<JfaFloodUniforms>()
             .add_system(extract_outline_settings.in_schedule(ExtractSchedule))
             .add_system(prepare_outline_textures.in_set(RenderSet::Prepare))
-            .add_system(prepare_blur_pipelines.in_set(RenderSet::Prepare));
+            .add_system(prepare_blur_pipelines.in_set(RenderSet::Prepare))
+            .add_system(prepare_max_filter_pipelines.in_set(RenderSet::Prepare))
+            .add_system(prepare_edge_detect_pipelines.in_set(RenderSet::Prepare))
+            .add_system(queue_edge_detect_fallback_images.in_set(RenderSet::Queue))
+            .add_system(prepare_jfa_flood_uniforms.in_set(RenderSet::Prepare));
+
+        // The node names are the same in both graphs
+        let (before, after): (&str, &[&str]) = match placement {
+            OutlinePassPlacement::BeforeTonemapping => (
+                core_3d::graph::node::MAIN_PASS,
+                &[
+                    core_3d::graph::node::BLOOM,
+                    core_3d::graph::node::TONEMAPPING,
+                ],
+            ),
+            OutlinePassPlacement::AfterTonemapping => (
+                core_3d::graph::node::TONEMAPPING,
+                &[
+                    core_3d::graph::node::FXAA,
+                    core_3d::graph::node::END_MAIN_PASS_POST_PROCESSING,
+                ],
+            ),
+            OutlinePassPlacement::AfterUi => (
+                draw_ui_graph::node::UI_PASS,
+                &[core_3d::graph::node::UPSCALING],
+            ),
+            OutlinePassPlacement::Manual => return,
+        };
+        for sub_graph_name in [core_3d::graph::NAME, core_2d::graph::NAME] {
+            add_outline_node(render_app, sub_graph_name);
 
-        {
-            let outline_node = OutlineNode::new(&mut render_app.world);
             let mut graph = render_app.world.resource_mut::<RenderGraph>();
-            let draw_3d_graph = graph.get_sub_graph_mut(core_3d::graph::NAME).unwrap();
+            let sub_graph = graph.get_sub_graph_mut(sub_graph_name).unwrap();
+            let outline = graph::node::OUTLINE_PASS;
+            let hull = graph::node::OUTLINE_HULL_PASS;
+            // The hulls are drawn with the multisampled view, before anything reads it
+            let edges = [
+                (core_3d::graph::node::MAIN_PASS, hull),
+                (hull, core_3d::graph::node::BLOOM),
+                (hull, core_3d::graph::node::TONEMAPPING),
+                (hull, outline),
+                (before, outline),
+            ]
+            .into_iter()
+            .chain(after.iter().map(|after| (outline, *after)));
+            for (from, to) in edges {
+                // The bloom and UI nodes only exist when their plugins are added before this one
+                if sub_graph.get_node_state(from).is_ok() && sub_graph.get_node_state(to).is_ok() {
+                    sub_graph.add_node_edge(from, to);
+                } else {
+                    warn!("Can't order the outline pass after {from} and before {to} in {sub_graph_name}, one of them is missing");
+                }
+            }
+        }
+    }
+}
 
-            draw_3d_graph.add_node(graph::node::OUTLINE_PASS, outline_node);
+/// Adds the [`OutlineNode`] and the [`OutlineHullNode`] to a sub graph of the render app,
+/// named [`graph::node::OUTLINE_PASS`] and [`graph::node::OUTLINE_HULL_PASS`].
+///
+/// The view entity is taken from the [`graph::input::VIEW_ENTITY`] input of the sub graph,
+/// the edges ordering the nodes with the other passes are left to the caller.
+/// The hull node must run right after the main pass, before the outline node and any post processing.
+pub fn add_outline_node(render_app: &mut App, sub_graph_name: &str) {
+    let outline_node = OutlineNode::new(&mut render_app.world);
+    let hull_node = OutlineHullNode::new(&mut render_app.world);
+    let mut graph = render_app.world.resource_mut::<RenderGraph>();
+    let sub_graph = graph.get_sub_graph_mut(sub_graph_name).unwrap();
 
-            draw_3d_graph.add_slot_edge(
-                draw_3d_graph.input_node().id,
-                graph::input::VIEW_ENTITY,
-                graph::node::OUTLINE_PASS,
-                OutlineNode::IN_VIEW,
-            );
+    sub_graph.add_node(graph::node::OUTLINE_PASS, outline_node);
+    sub_graph.add_node(graph::node::OUTLINE_HULL_PASS, hull_node);
 
-            draw_3d_graph.add_node_edge(core_3d::graph::node::MAIN_PASS, graph::node::OUTLINE_PASS);
-        }
-    }
+    let input_node = sub_graph.input_node().id;
+    sub_graph.add_slot_edge(
+        input_node,
+        graph::input::VIEW_ENTITY,
+        graph::node::OUTLINE_PASS,
+        OutlineNode::IN_VIEW,
+    );
+    sub_graph.add_slot_edge(
+        input_node,
+        graph::input::VIEW_ENTITY,
+        graph::node::OUTLINE_HULL_PASS,
+        OutlineHullNode::IN_VIEW,
+    );
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Default)]
@@ -117,51 +485,287 @@ pub enum OutlineType {
     #[default]
     BoxBlur,
     GaussianBlur,
+    /// Spreads the stencil in a vertical then a horizontal pass, the cost grows linearly with the size
     MaxFilter,
+    /// Jump Flood Algorithm. The cost only grows logarithmically with the size,
+    /// this is the best option for very wide outlines.
     Jfa,
+    /// Draws the meshes a second time, extruded along their normals and with their front faces culled.
+    ///
+    /// There's no fullscreen pass, the hull is part of the scene and is hidden by anything in front of it.
+    /// Only 3d meshes are outlined, their normals must be smooth for the hull to stay closed.
+    /// The [`OutlineStyle`], [`OutlineSide`], [`OutlineDownsample`], [`OutlineOcclusion`] and dashes are ignored.
+    InvertedHull,
+    /// Draws lines on the edges of the whole scene, not only on the entities with an [`Outline`].
+    ///
+    /// The edges are found with a Sobel filter over the depth, the normals and the color of the view,
+    /// see [`OutlineEdgeDetection`]. The size is the distance between the samples of the filter in pixels.
+    /// The entities with an [`Outline`] in the same layer don't get any line.
+    EdgeDetect,
+}
+
+/// Thresholds used by [`OutlineType::EdgeDetect`] to decide where to draw a line, a threshold of 0 ignores its source.
+///
+/// The depth and the normals require the [`DepthPrepass`] and [`NormalPrepass`] components on the camera,
+/// without them only the color is used.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct OutlineEdgeDetection {
+    pub color: Color,
+    /// Difference of distance to the camera, relative to the distance of the pixel
+    pub depth_threshold: f32,
+    /// Difference between the normals, 1 is roughly a right angle
+    pub normal_threshold: f32,
+    /// Difference of luminance of the view
+    pub color_threshold: f32,
+}
+
+impl Default for OutlineEdgeDetection {
+    fn default() -> Self {
+        Self {
+            color: Color::BLACK,
+            depth_threshold: 0.1,
+            normal_threshold: 0.4,
+            color_threshold: 0.0,
+        }
+    }
 }
 
-#[derive(Component, Clone, Copy, Debug, Default, ExtractComponent)]
+/// Unit of the [`OutlineSettings::size`] and of the [`Outline::width`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Default)]
+pub enum OutlineWidthUnit {
+    #[default]
+    PhysicalPixels,
+    /// Pixels scaled by the scale factor of the window, the outlines keep their size on high DPI screens
+    LogicalPixels,
+    /// The width is projected at the depth of each entity, the outline gets thinner with the distance to the camera.
+    /// With a perspective camera, [`OutlineType::MaxFilter`] and [`OutlineType::Jfa`] outlines are at most 64 physical pixels wide.
+    ///
+    /// Only supported by [`OutlineType::MaxFilter`], [`OutlineType::Jfa`] and [`OutlineType::InvertedHull`],
+    /// the other types don't have per-entity widths and use physical pixels.
+    World,
+}
+
+/// Fades the outlines with the distance between the outlined entities and the camera.
+/// In 2d, the distance is measured in the plane of the sprites.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct OutlineFade {
+    /// Closer outlines aren't faded
+    pub start: f32,
+    /// Further outlines are completely faded
+    pub end: f32,
+    /// The width shrinks with the distance.
+    /// Only supported by [`OutlineType::MaxFilter`], [`OutlineType::Jfa`] and [`OutlineType::InvertedHull`].
+    pub width: bool,
+    /// The alpha of the color decreases with the distance
+    pub alpha: bool,
+}
+
+impl Default for OutlineFade {
+    fn default() -> Self {
+        Self {
+            start: 10.0,
+            end: 30.0,
+            width: true,
+            alpha: true,
+        }
+    }
+}
+
+/// Resolution of the textures used by blurred outlines, relative to the viewport.
+///
+/// Blurring a smaller texture is a lot cheaper, the result is bilinearly upsampled when combined
+/// with the view. This is mostly useful for wide glows, thin outlines will look blocky.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Default)]
+pub enum OutlineDownsample {
+    #[default]
+    None,
+    Half,
+    Quarter,
+}
+
+impl OutlineDownsample {
+    pub fn factor(&self) -> u32 {
+        match self {
+            OutlineDownsample::None => 1,
+            OutlineDownsample::Half => 2,
+            OutlineDownsample::Quarter => 4,
+        }
+    }
+
+    /// Size of a texture downsampled from the given viewport size, it's never smaller than 1 pixel
+    fn texture_size(&self, viewport_size: UVec2) -> UVec2 {
+        let factor = self.factor();
+        ((viewport_size + (factor - 1)) / factor).max(UVec2::ONE)
+    }
+}
+
+/// Which side of the edge of the stencil is outlined
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Default)]
+pub enum OutlineSide {
+    /// The outline surrounds the entity
+    #[default]
+    Outer,
+    /// The outline is a band inside the entity along its edges. It uses the color of the entity's [`Outline`]
+    Inner,
+    Both,
+}
+
+impl OutlineSide {
+    fn has_outer(&self) -> bool {
+        matches!(self, OutlineSide::Outer | OutlineSide::Both)
+    }
+
+    fn has_inner(&self) -> bool {
+        matches!(self, OutlineSide::Inner | OutlineSide::Both)
+    }
+}
+
+/// Shape of the area covered by the outline around each pixel of an entity, it's visible at its corners.
+///
+/// Only used by [`OutlineType::MaxFilter`] and [`OutlineType::Jfa`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Default)]
+pub enum OutlineKernelShape {
+    /// The outline of [`OutlineType::MaxFilter`] is the same as without a kernel shape
+    #[default]
+    Square,
+    /// The outline is equally thick in every direction
+    Circle,
+    Diamond,
+}
+
+impl OutlineKernelShape {
+    /// Distance used to decide if an offset is covered by an outline, it must match `kernel_distance` in the shaders
+    pub fn distance(&self, offset: Vec2) -> f32 {
+        let offset = offset.abs();
+        match self {
+            OutlineKernelShape::Square => offset.max_element(),
+            OutlineKernelShape::Circle => offset.length(),
+            OutlineKernelShape::Diamond => offset.x + offset.y,
+        }
+    }
+
+    // Must match the constants in max_filter.wgsl and jfa.wgsl
+    fn as_u32(&self) -> u32 {
+        match self {
+            OutlineKernelShape::Square => 0,
+            OutlineKernelShape::Circle => 1,
+            OutlineKernelShape::Diamond => 2,
+        }
+    }
+}
+
+/// Controls how the outline is blended with the rest of the scene
+#[derive(Clone, Copy, Debug, PartialEq, Default)]
+pub enum OutlineStyle {
+    /// The outline fades out the further it is from the mesh. The fade depends on the [`OutlineType`]
+    #[default]
+    Glow,
+    /// A fully opaque outline with a hard edge.
+    ///
+    /// The softness is the width of the antialiased edge in pixels, use 0.0 for a perfectly hard edge.
+    Solid { softness: f32 },
+}
+
+#[derive(Component, Clone, Copy, Debug, Default)]
 pub struct OutlineSettings {
     // The size or thickness of the outline, higher numbers will create wider outlines
     pub size: f32,
     // The intensity of the outline. Only useful for blurred outlines. Does nothing for other types of outline.
+    // Solid outlines are always fully opaque.
     pub intensity: f32,
     pub outline_type: OutlineType,
+    pub style: OutlineStyle,
+    /// Only used by [`OutlineType::BoxBlur`] and [`OutlineType::GaussianBlur`]
+    pub downsample: OutlineDownsample,
+    /// Drawing both sides runs the outline passes twice
+    pub side: OutlineSide,
+    /// The shape of the corners of the outline
+    pub kernel_shape: OutlineKernelShape,
+    pub width_unit: OutlineWidthUnit,
+    /// Fades the outlines of far entities, they aren't faded when `None`
+    pub fade: Option<OutlineFade>,
+    /// Only used by [`OutlineType::EdgeDetect`]
+    pub edge_detection: OutlineEdgeDetection,
 }
 
 #[derive(Component, ShaderType, Clone)]
 struct CombineSettingsUniform {
     intensity: f32,
+    solid: u32,
+    softness: f32,
+    outer: u32,
+    inner: u32,
 }
 
 #[derive(Component, ShaderType, Clone)]
 struct MaxFilterSettingsUniform {
     size: f32,
     dims: Vec2,
-    viewport: Vec4,
+    kernel_shape: u32,
+}
+
+#[derive(Component, ShaderType, Clone)]
+struct EdgeDetectSettingsUniform {
+    color: Vec4,
+    inverse_projection: Mat4,
+    /// The prepass and the view cover the whole target while the output only covers the viewport
+    viewport_origin: Vec2,
+    offset: f32,
+    depth_threshold: f32,
+    normal_threshold: f32,
+    color_threshold: f32,
 }
 
 #[derive(Component)]
 pub struct StencilTexture {
     texture: CachedTexture,
     texture_sampled: Option<CachedTexture>,
+    /// Per pixel data of the outline, like the width
+    data_texture: CachedTexture,
+    data_texture_sampled: Option<CachedTexture>,
+    /// Per pixel rank of the outline, see [`OutlineOverlap`]
+    rank_texture: CachedTexture,
+    rank_texture_sampled: Option<CachedTexture>,
+    /// Only used during the stencil pass, it has the same sample count as the other attachments
+    depth_texture: CachedTexture,
 }
 
 impl StencilTexture {
-    fn get_color_attachment(&self) -> Option<RenderPassColorAttachment<'_>> {
+    fn get_color_attachments(&self) -> [Option<RenderPassColorAttachment<'_>>; 3] {
+        [
+            Self::color_attachment(&self.texture, self.texture_sampled.as_ref()),
+            Self::color_attachment(&self.data_texture, self.data_texture_sampled.as_ref()),
+            Self::color_attachment(&self.rank_texture, self.rank_texture_sampled.as_ref()),
+        ]
+    }
+
+    fn get_depth_stencil_attachment(&self) -> RenderPassDepthStencilAttachment<'_> {
+        RenderPassDepthStencilAttachment {
+            view: &self.depth_texture.default_view,
+            depth_ops: Some(Operations {
+                load: LoadOp::Clear(0.0),
+                store: false,
+            }),
+            stencil_ops: None,
+        }
+    }
+
+    fn color_attachment<'a>(
+        texture: &'a CachedTexture,
+        texture_sampled: Option<&'a CachedTexture>,
+    ) -> Option<RenderPassColorAttachment<'a>> {
         let ops = Operations {
             load: LoadOp::Clear(Color::NONE.into()),
             store: true,
         };
-        match self.texture_sampled.as_ref() {
+        match texture_sampled {
             Some(CachedTexture { default_view, .. }) => Some(RenderPassColorAttachment {
                 view: default_view,
-                resolve_target: Some(&self.texture.default_view),
+                resolve_target: Some(&texture.default_view),
                 ops,
             }),
             None => Some(RenderPassColorAttachment {
-                view: &self.texture.default_view,
+                view: &texture.default_view,
                 resolve_target: None,
                 ops,
             }),
@@ -171,17 +775,77 @@ impl StencilTexture {
 
 #[derive(Component)]
 struct BlurredOutlineTextures {
-    vertical_blur_texture: CachedTexture,
+    /// The output of the first pass, only used by the blurs and [`OutlineType::MaxFilter`]
+    vertical_blur_texture: Option<CachedTexture>,
     horizontal_blur_texture: CachedTexture,
+    /// The outline inside the stencil, only used by [`OutlineSide::Inner`] and [`OutlineSide::Both`]
+    inner_texture: Option<CachedTexture>,
+    /// The lines drawn inside the stencil, the [`Outline::interior_lines`] and the edges between [`OutlineGroup`]s.
+    /// Only used when the layer has any of them.
+    interior_lines_texture: Option<CachedTexture>,
+    /// The layer has outlines of several [`OutlineGroup`]s, their edges are drawn in the `interior_lines_texture`
+    group_edges: bool,
+}
+
+/// The data and rank of the outline spread by the vertical max filter pass, read by the horizontal pass.
+/// The color is stored in the `vertical_blur_texture`.
+#[derive(Component)]
+struct MaxFilterTextures {
+    data: CachedTexture,
+    rank: CachedTexture,
+}
+
+/// Ping-pong textures storing the position of the closest seed for each pixel
+#[derive(Component)]
+struct JfaTextures {
+    ping: CachedTexture,
+    pong: CachedTexture,
+}
+
+/// A pipeline writing the colors of the outlines, queued for the texture formats of both LDR and HDR views
+#[derive(Clone, Copy)]
+struct HdrPipelineIds {
+    ldr: CachedRenderPipelineId,
+    hdr: CachedRenderPipelineId,
+}
+
+impl HdrPipelineIds {
+    fn queue(
+        pipeline_cache: &PipelineCache,
+        descriptor: impl Fn(bool) -> RenderPipelineDescriptor,
+    ) -> Self {
+        Self {
+            ldr: pipeline_cache.queue_render_pipeline(descriptor(false)),
+            hdr: pipeline_cache.queue_render_pipeline(descriptor(true)),
+        }
+    }
+
+    fn get(&self, hdr: bool) -> CachedRenderPipelineId {
+        match hdr {
+            true => self.hdr,
+            false => self.ldr,
+        }
+    }
 }
 
 #[derive(Resource)]
 struct OutlineMeta {
     sampler: Sampler,
-    max_filter_bind_group_layout: BindGroupLayout,
-    max_filter_pipeline: CachedRenderPipelineId,
     combine_bind_group_layout: BindGroupLayout,
-    combine_pipeline: CachedRenderPipelineId,
+    combine_pipeline: HdrPipelineIds,
+    /// Also draws the interior lines of the layer
+    combine_interior_lines_pipeline: HdrPipelineIds,
+    jfa_seed_bind_group_layout: BindGroupLayout,
+    jfa_seed_pipeline: CachedRenderPipelineId,
+    jfa_inner_seed_pipeline: CachedRenderPipelineId,
+    jfa_flood_bind_group_layout: BindGroupLayout,
+    jfa_flood_pipeline: CachedRenderPipelineId,
+    jfa_outline_bind_group_layout: BindGroupLayout,
+    jfa_outline_pipeline: HdrPipelineIds,
+    jfa_inner_outline_pipeline: HdrPipelineIds,
+    group_edges_bind_group_layout: BindGroupLayout,
+    /// Blends the edges between groups over the interior lines
+    group_edges_pipeline: HdrPipelineIds,
 }
 
 impl FromWorld for OutlineMeta {
@@ -200,115 +864,356 @@ impl FromWorld for OutlineMeta {
             view_dimension: TextureViewDimension::D2,
             multisampled: false,
         };
+        let jfa_texture = BindingType::Texture {
+            sample_type: TextureSampleType::Float { filterable: false },
+            view_dimension: TextureViewDimension::D2,
+            multisampled: false,
+        };
+        let max_filter_settings = BindingType::Buffer {
+            ty: BufferBindingType::Uniform,
+            has_dynamic_offset: true,
+            min_binding_size: Some(MaxFilterSettingsUniform::min_size()),
+        };
 
-        let max_filter_bind_group_layout =
+        let combine_bind_group_layout =
             render_device.create_bind_group_layout(&BindGroupLayoutDescriptor {
-                label: Some("max_filter_bind_group_layout"),
+                label: Some("combine_bind_group_layout"),
                 entries: &bind_group_layout_entries![
-                    // input texture
-                    0 => texture,
                     // sampler
-                    1 => BindingType::Sampler(SamplerBindingType::Filtering),
-                    // uniform
-                    2 => BindingType::Buffer {
+                    0 => BindingType::Sampler(SamplerBindingType::Filtering),
+                    // stencil texture
+                    1 => texture,
+                    // blur texture
+                    2 => texture,
+                    // settings
+                    3 => BindingType::Buffer {
                         ty: BufferBindingType::Uniform,
                         has_dynamic_offset: true,
-                        min_binding_size: Some(MaxFilterSettingsUniform::min_size()),
+                        min_binding_size: Some(CombineSettingsUniform::min_size()),
                     },
+                    // inner outline texture
+                    4 => texture,
+                    // stencil data texture
+                    5 => texture,
+                    // interior lines texture
+                    6 => texture,
                 ],
             });
 
-        let combine_bind_group_layout =
+        // The bindings match the ones declared in jfa.wgsl,
+        // each entry point only uses a subset of them
+        let jfa_seed_bind_group_layout =
             render_device.create_bind_group_layout(&BindGroupLayoutDescriptor {
-                label: Some("combine_bind_group_layout"),
+                label: Some("jfa_seed_bind_group_layout"),
                 entries: &bind_group_layout_entries![
+                    // stencil data texture
+                    1 => texture,
                     // sampler
-                    0 => BindingType::Sampler(SamplerBindingType::Filtering),
+                    2 => BindingType::Sampler(SamplerBindingType::Filtering),
+                    // settings
+                    3 => max_filter_settings,
+                ],
+            });
+
+        let jfa_flood_bind_group_layout =
+            render_device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+                label: Some("jfa_flood_bind_group_layout"),
+                entries: &bind_group_layout_entries![
+                    // jfa texture
+                    0 => jfa_texture,
+                    // settings
+                    3 => max_filter_settings,
+                    // step
+                    4 => BindingType::Buffer {
+                        ty: BufferBindingType::Uniform,
+                        has_dynamic_offset: true,
+                        min_binding_size: Some(JfaFloodUniform::min_size()),
+                    },
+                    // stencil data texture
+                    5 => texture,
+                    // stencil rank texture
+                    7 => texture,
+                ],
+            });
+
+        let jfa_outline_bind_group_layout =
+            render_device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+                label: Some("jfa_outline_bind_group_layout"),
+                entries: &bind_group_layout_entries![
+                    // jfa texture
+                    0 => jfa_texture,
                     // stencil texture
                     1 => texture,
-                    // blur texture
-                    2 => texture,
-                    // intensity
-                    3 => BindingType::Buffer {
+                    // sampler
+                    2 => BindingType::Sampler(SamplerBindingType::Filtering),
+                    // settings
+                    3 => max_filter_settings,
+                    // stencil data texture
+                    5 => texture,
+                    // globals
+                    6 => BindingType::Buffer {
                         ty: BufferBindingType::Uniform,
-                        has_dynamic_offset: true,
-                        min_binding_size: Some(CombineSettingsUniform::min_size()),
+                        has_dynamic_offset: false,
+                        min_binding_size: Some(GlobalsUniform::min_size()),
                     },
                 ],
             });
 
+        let group_edges_bind_group_layout =
+            render_device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+                label: Some("group_edges_bind_group_layout"),
+                entries: &bind_group_layout_entries![
+                    // stencil texture
+                    0 => texture,
+                    // stencil data texture
+                    1 => texture,
+                    // stencil rank texture
+                    2 => texture,
+                    // settings
+                    3 => max_filter_settings,
+                ],
+            });
+
         let pipeline_cache = world.resource::<PipelineCache>();
 
-        let max_filter_pipeline = pipeline_cache.queue_render_pipeline(
+        let combine_pipeline = HdrPipelineIds::queue(pipeline_cache, |hdr| {
             RenderPipelineDescriptorBuilder::fullscreen()
-                .label("max_filter_pipeline".into())
+                .label("combine_pipeline".into())
                 .fragment(
-                    MAX_FILTER_SHADER_HANDLE,
-                    "fragment",
-                    &[color_target(None)],
+                    COMBINE_SHADER_HANDLE,
+                    "combine",
+                    // Additive blending
+                    &[color_target(
+                        Some(BlendState::PREMULTIPLIED_ALPHA_BLENDING),
+                        hdr,
+                    )],
                     &[],
                 )
-                .layout(vec![max_filter_bind_group_layout.clone()])
-                .build(),
-        );
+                .layout(vec![combine_bind_group_layout.clone()])
+                .build()
+        });
 
-        let combine_pipeline = pipeline_cache.queue_render_pipeline(
+        let combine_interior_lines_pipeline = HdrPipelineIds::queue(pipeline_cache, |hdr| {
             RenderPipelineDescriptorBuilder::fullscreen()
-                .label("combine_pipeline".into())
+                .label("combine_interior_lines_pipeline".into())
                 .fragment(
                     COMBINE_SHADER_HANDLE,
                     "combine",
-                    // Additive blending
-                    &[color_target(Some(BlendState::PREMULTIPLIED_ALPHA_BLENDING))],
-                    &[],
+                    &[color_target(
+                        Some(BlendState::PREMULTIPLIED_ALPHA_BLENDING),
+                        hdr,
+                    )],
+                    &["INTERIOR_LINES".into()],
                 )
                 .layout(vec![combine_bind_group_layout.clone()])
+                .build()
+        });
+
+        let jfa_seed_pipeline = pipeline_cache.queue_render_pipeline(
+            RenderPipelineDescriptorBuilder::fullscreen()
+                .label("jfa_seed_pipeline".into())
+                .fragment(JFA_SHADER_HANDLE, "seed", &[jfa_color_target()], &[])
+                .layout(vec![jfa_seed_bind_group_layout.clone()])
                 .build(),
         );
 
+        let jfa_inner_seed_pipeline = pipeline_cache.queue_render_pipeline(
+            RenderPipelineDescriptorBuilder::fullscreen()
+                .label("jfa_inner_seed_pipeline".into())
+                .fragment(
+                    JFA_SHADER_HANDLE,
+                    "seed",
+                    &[jfa_color_target()],
+                    &["INNER".into()],
+                )
+                .layout(vec![jfa_seed_bind_group_layout.clone()])
+                .build(),
+        );
+
+        let jfa_flood_pipeline = pipeline_cache.queue_render_pipeline(
+            RenderPipelineDescriptorBuilder::fullscreen()
+                .label("jfa_flood_pipeline".into())
+                .fragment(JFA_SHADER_HANDLE, "flood", &[jfa_color_target()], &[])
+                .layout(vec![jfa_flood_bind_group_layout.clone()])
+                .build(),
+        );
+
+        let jfa_outline_pipeline = HdrPipelineIds::queue(pipeline_cache, |hdr| {
+            RenderPipelineDescriptorBuilder::fullscreen()
+                .label("jfa_outline_pipeline".into())
+                .fragment(
+                    JFA_SHADER_HANDLE,
+                    "outline",
+                    &[color_target(None, hdr)],
+                    &[],
+                )
+                .layout(vec![jfa_outline_bind_group_layout.clone()])
+                .build()
+        });
+
+        let jfa_inner_outline_pipeline = HdrPipelineIds::queue(pipeline_cache, |hdr| {
+            RenderPipelineDescriptorBuilder::fullscreen()
+                .label("jfa_inner_outline_pipeline".into())
+                .fragment(
+                    JFA_SHADER_HANDLE,
+                    "outline",
+                    &[color_target(None, hdr)],
+                    &["INNER".into()],
+                )
+                .layout(vec![jfa_outline_bind_group_layout.clone()])
+                .build()
+        });
+
+        let group_edges_pipeline = HdrPipelineIds::queue(pipeline_cache, |hdr| {
+            RenderPipelineDescriptorBuilder::fullscreen()
+                .label("group_edges_pipeline".into())
+                .fragment(
+                    GROUP_EDGES_SHADER_HANDLE,
+                    "fragment",
+                    &[color_target(
+                        Some(BlendState::PREMULTIPLIED_ALPHA_BLENDING),
+                        hdr,
+                    )],
+                    &[],
+                )
+                .layout(vec![group_edges_bind_group_layout.clone()])
+                .build()
+        });
+
         Self {
             sampler,
-            max_filter_bind_group_layout,
-            max_filter_pipeline,
             combine_bind_group_layout,
             combine_pipeline,
+            combine_interior_lines_pipeline,
+            jfa_seed_bind_group_layout,
+            jfa_seed_pipeline,
+            jfa_inner_seed_pipeline,
+            jfa_flood_bind_group_layout,
+            jfa_flood_pipeline,
+            jfa_outline_bind_group_layout,
+            jfa_outline_pipeline,
+            jfa_inner_outline_pipeline,
+            group_edges_bind_group_layout,
+            group_edges_pipeline,
         }
     }
 }
 
+/// The render world entity of an [`OutlineLayer`] of a view.
+///
+/// It holds everything needed to draw the outlines of a single layer.
+#[derive(Component)]
+struct ViewOutlineLayer {
+    view: Entity,
+    layer: OutlineLayer,
+}
+
+/// The entities with a [`ViewOutlineLayer`] of a view, sorted by layer
+#[derive(Component)]
+struct ViewOutlineLayers(Vec<Entity>);
+
 fn extract_outline_settings(
     mut commands: Commands,
-    cameras: Extract<Query<(Entity, &Camera, &OutlineSettings), With<Camera3d>>>,
+    cameras: Extract<
+        Query<
+            (
+                Entity,
+                &Camera,
+                Option<&OutlineSettings>,
+                Option<&OutlineLayers>,
+            ),
+            Or<(With<Camera3d>, With<Camera2d>)>,
+        >,
+    >,
 ) {
-    for (entity, camera, settings) in cameras.iter() {
-        if let (Some((origin, _)), Some(size), Some(target_size)) = (
-            camera.physical_viewport_rect(),
-            camera.physical_viewport_size(),
-            camera.physical_target_size(),
-        ) {
-            let viewport = UVec4::new(origin.x, origin.y, size.x, size.y).as_vec4()
-                / UVec4::new(target_size.x, target_size.y, target_size.x, target_size.y).as_vec4();
-            commands
-                .get_or_spawn(entity)
+    for (entity, camera, settings, layers) in cameras.iter() {
+        let Some(viewport) = OutlineViewport::from_camera(camera) else {
+            continue;
+        };
+
+        let mut layer_settings: Vec<_> = layers
+            .map(|layers| layers.0.clone().into_iter().collect())
+            .unwrap_or_default();
+        if let Some(settings) = settings {
+            let default_layer = OutlineLayer::default();
+            if !layer_settings
+                .iter()
+                .any(|(layer, _)| *layer == default_layer)
+            {
+                layer_settings.push((OutlineLayer::default(), *settings));
+            }
+        }
+        if layer_settings.is_empty() {
+            continue;
+        }
+        layer_settings.sort_by_key(|(layer, _)| *layer);
+
+        let mut layer_entities = Vec::with_capacity(layer_settings.len());
+        for (layer, settings) in layer_settings {
+            let projection = camera.projection_matrix();
+            // Blurs and edge detection don't have per-entity widths, they can't be in world units
+            let screen_size = match settings.width_unit {
+                OutlineWidthUnit::LogicalPixels => settings.size * viewport.scale_factor,
+                OutlineWidthUnit::PhysicalPixels | OutlineWidthUnit::World => settings.size,
+            };
+            // The size of the blur is in pixels of the downsampled texture
+            let blur_texture_size = viewport.outline_texture_size(&settings);
+            let layer_entity = commands
+                .spawn(ViewOutlineLayer {
+                    view: entity,
+                    layer,
+                })
                 .insert(BlurUniform {
-                    size: settings.size,
-                    dims: Vec2::ONE / size.as_vec2(),
-                    viewport,
+                    size: screen_size / settings.downsample.factor() as f32,
+                    dims: Vec2::ONE / blur_texture_size.as_vec2(),
                 })
                 .insert(CombineSettingsUniform {
                     intensity: settings.intensity,
+                    solid: matches!(settings.style, OutlineStyle::Solid { .. }).into(),
+                    softness: match settings.style {
+                        OutlineStyle::Glow => 0.0,
+                        OutlineStyle::Solid { softness } => softness.max(0.0),
+                    },
+                    // The edges are always drawn as an outer outline, there's nothing to invert
+                    outer: (settings.side.has_outer()
+                        || settings.outline_type == OutlineType::EdgeDetect)
+                        .into(),
+                    inner: (settings.side.has_inner()
+                        && settings.outline_type != OutlineType::EdgeDetect)
+                        .into(),
                 })
                 .insert(MaxFilterSettingsUniform {
                     size: match settings.outline_type {
-                        OutlineType::BoxBlur | OutlineType::GaussianBlur => settings.size / 2.0,
-                        OutlineType::MaxFilter => settings.size,
-                        OutlineType::Jfa => 0.0,
+                        OutlineType::BoxBlur | OutlineType::GaussianBlur => screen_size / 2.0,
+                        // The widths are converted to pixels by the stencil, this is the widest one
+                        OutlineType::MaxFilter | OutlineType::Jfa => max_pixel_width(
+                            settings.size,
+                            settings.width_unit,
+                            projection,
+                            &viewport,
+                        ),
+                        OutlineType::InvertedHull | OutlineType::EdgeDetect => screen_size,
                     },
-                    dims: Vec2::ONE / size.as_vec2(),
-                    viewport,
+                    dims: Vec2::ONE / viewport.size.as_vec2(),
+                    kernel_shape: settings.kernel_shape.as_u32(),
+                })
+                .insert(EdgeDetectSettingsUniform {
+                    color: settings.edge_detection.color.as_linear_rgba_f32().into(),
+                    inverse_projection: projection.inverse(),
+                    viewport_origin: viewport.origin.as_vec2(),
+                    offset: (screen_size * 0.5).max(1.0),
+                    depth_threshold: settings.edge_detection.depth_threshold,
+                    normal_threshold: settings.edge_detection.normal_threshold,
+                    color_threshold: settings.edge_detection.color_threshold,
                 })
-                .insert(*settings);
+                .insert(StencilLayerUniform::new(&settings, projection, &viewport))
+                .insert(settings)
+                .id();
+            layer_entities.push(layer_entity);
         }
+        commands
+            .get_or_spawn(entity)
+            .insert((ViewOutlineLayers(layer_entities), viewport));
     }
 }
 
@@ -316,6 +1221,8 @@ fn extract_outline_settings(
 struct BlurPipelines {
     vertical_blur_pipeline_id: CachedRenderPipelineId,
     horizontal_blur_pipeline_id: CachedRenderPipelineId,
+    /// Vertical blur of the inverted stencil, used as the first pass of inner outlines
+    inner_vertical_blur_pipeline_id: CachedRenderPipelineId,
 }
 
 fn prepare_blur_pipelines(
@@ -323,14 +1230,18 @@ fn prepare_blur_pipelines(
     pipeline_cache: Res<PipelineCache>,
     mut pipelines: ResMut<SpecializedRenderPipelines<BlurPipeline>>,
     blur_pipeline: Res<BlurPipeline>,
-    views: Query<(Entity, &OutlineSettings)>,
+    layers: Query<(Entity, &ViewOutlineLayer, &OutlineSettings)>,
+    views: Query<&ExtractedView>,
 ) {
-    for (entity, settings) in &views {
+    for (entity, layer, settings) in &layers {
         let blur_type = match settings.outline_type {
             OutlineType::BoxBlur => BlurType::Box,
-            OutlineType::GaussianBlur { .. } => BlurType::Gaussian,
+            OutlineType::GaussianBlur => BlurType::Gaussian,
             _ => continue,
         };
+        let Ok(view) = views.get(layer.view) else {
+            continue;
+        };
 
         let vertical_blur_pipeline_id = pipelines.specialize(
             &pipeline_cache,
@@ -338,6 +1249,8 @@ fn prepare_blur_pipelines(
             BlurPipelineKey {
                 blur_type,
                 direction: BlurDirection::Vertical,
+                inner: false,
+                hdr: view.hdr,
             },
         );
         let horizontal_blur_pipeline_id = pipelines.specialize(
@@ -346,28 +1259,157 @@ fn prepare_blur_pipelines(
             BlurPipelineKey {
                 blur_type,
                 direction: BlurDirection::Horizontal,
+                inner: false,
+                hdr: view.hdr,
+            },
+        );
+        let inner_vertical_blur_pipeline_id = pipelines.specialize(
+            &pipeline_cache,
+            &blur_pipeline,
+            BlurPipelineKey {
+                blur_type,
+                direction: BlurDirection::Vertical,
+                inner: true,
+                hdr: view.hdr,
             },
         );
 
         commands.entity(entity).insert(BlurPipelines {
             vertical_blur_pipeline_id,
             horizontal_blur_pipeline_id,
+            inner_vertical_blur_pipeline_id,
+        });
+    }
+}
+
+#[derive(Component)]
+struct MaxFilterPipelines {
+    vertical_pipeline_id: CachedRenderPipelineId,
+    horizontal_pipeline_id: CachedRenderPipelineId,
+    inner_vertical_pipeline_id: CachedRenderPipelineId,
+    inner_horizontal_pipeline_id: CachedRenderPipelineId,
+}
+
+fn prepare_max_filter_pipelines(
+    mut commands: Commands,
+    pipeline_cache: Res<PipelineCache>,
+    mut pipelines: ResMut<SpecializedRenderPipelines<MaxFilterPipeline>>,
+    max_filter_pipeline: Res<MaxFilterPipeline>,
+    layers: Query<(Entity, &ViewOutlineLayer, &OutlineSettings)>,
+    views: Query<&ExtractedView>,
+) {
+    for (entity, layer, settings) in &layers {
+        if settings.outline_type != OutlineType::MaxFilter {
+            continue;
+        }
+        let Ok(view) = views.get(layer.view) else {
+            continue;
+        };
+
+        let mut specialize = |direction, inner| {
+            pipelines.specialize(
+                &pipeline_cache,
+                &max_filter_pipeline,
+                MaxFilterPipelineKey {
+                    direction,
+                    inner,
+                    hdr: view.hdr,
+                },
+            )
+        };
+        commands.entity(entity).insert(MaxFilterPipelines {
+            vertical_pipeline_id: specialize(BlurDirection::Vertical, false),
+            horizontal_pipeline_id: specialize(BlurDirection::Horizontal, false),
+            inner_vertical_pipeline_id: specialize(BlurDirection::Vertical, true),
+            inner_horizontal_pipeline_id: specialize(BlurDirection::Horizontal, true),
         });
     }
 }
 
-/// Prepares the textures used to render the outline
+#[derive(Component)]
+struct EdgeDetectPipelineId(CachedRenderPipelineId);
+
+#[derive(Component)]
+struct InteriorLinesPipelineId(CachedRenderPipelineId);
+
+fn prepare_edge_detect_pipelines(
+    mut commands: Commands,
+    pipeline_cache: Res<PipelineCache>,
+    mut pipelines: ResMut<SpecializedRenderPipelines<EdgeDetectPipeline>>,
+    edge_detect_pipeline: Res<EdgeDetectPipeline>,
+    interior_lines_layers: Res<InteriorLinesLayers>,
+    layers: Query<(Entity, &ViewOutlineLayer, &OutlineSettings)>,
+    views: Query<(
+        &ExtractedView,
+        Option<&DepthPrepass>,
+        Option<&NormalPrepass>,
+    )>,
+    msaa: Res<Msaa>,
+) {
+    for (entity, layer, settings) in &layers {
+        let edge_detect = settings.outline_type == OutlineType::EdgeDetect;
+        let interior_lines = interior_lines_layers.0.contains(&layer.layer)
+            && settings.outline_type != OutlineType::InvertedHull;
+        if !edge_detect && !interior_lines {
+            continue;
+        }
+        let Ok((view, depth_prepass, normal_prepass)) = views.get(layer.view) else {
+            continue;
+        };
+
+        let mut specialize = |interior_lines| {
+            pipelines.specialize(
+                &pipeline_cache,
+                &edge_detect_pipeline,
+                EdgeDetectPipelineKey {
+                    depth_prepass: depth_prepass.is_some(),
+                    normal_prepass: normal_prepass.is_some(),
+                    multisampled: msaa.samples() > 1,
+                    interior_lines,
+                    hdr: view.hdr,
+                },
+            )
+        };
+        let mut entity_commands = commands.entity(entity);
+        if edge_detect {
+            entity_commands.insert(EdgeDetectPipelineId(specialize(false)));
+        }
+        if interior_lines {
+            entity_commands.insert(InteriorLinesPipelineId(specialize(true)));
+        }
+    }
+}
+
+/// Makes sure the fallback prepass textures exist, they are bound by [`OutlineType::EdgeDetect`] when a view doesn't have a prepass
+fn queue_edge_detect_fallback_images(
+    mut fallback_images: FallbackImagesMsaa,
+    mut fallback_depths: FallbackImagesDepth,
+    msaa: Res<Msaa>,
+) {
+    fallback_images.image_for_samplecount(msaa.samples());
+    fallback_depths.image_for_samplecount(msaa.samples());
+}
+
+/// Prepares the textures used to render the outline of every layer
 fn prepare_outline_textures(
     mut commands: Commands,
     render_device: Res<RenderDevice>,
     mut texture_cache: ResMut<TextureCache>,
-    cameras: Query<(Entity, &ExtractedCamera, &OutlineSettings)>,
+    layers: Query<(Entity, &ViewOutlineLayer, &OutlineSettings)>,
+    views: Query<(&OutlineViewport, &ExtractedView)>,
+    interior_lines_layers: Res<InteriorLinesLayers>,
+    group_edges_layers: Res<GroupEdgesLayers>,
     msaa: Res<Msaa>,
 ) {
-    for (entity, camera, settings) in &cameras {
-        let Some(UVec2 { x, y }) = camera.physical_viewport_size else {
+    for (entity, layer, settings) in &layers {
+        // Inverted hulls are drawn directly to the view
+        if settings.outline_type == OutlineType::InvertedHull {
+            continue;
+        }
+        let Ok((viewport, view)) = views.get(layer.view) else {
             continue;
         };
+        let UVec2 { x, y } = viewport.size;
 
         let base_desc = TextureDescriptor {
             label: None,
@@ -379,7 +1421,7 @@ fn prepare_outline_textures(
             mip_level_count: 1,
             sample_count: 1,
             dimension: TextureDimension::D2,
-            format: TextureFormat::bevy_default(),
+            format: color_texture_format(view.hdr),
             usage: TextureUsages::RENDER_ATTACHMENT | TextureUsages::TEXTURE_BINDING,
             view_formats: &[],
         };
@@ -392,6 +1434,41 @@ fn prepare_outline_textures(
             },
         );
 
+        let data_desc = TextureDescriptor {
+            format: STENCIL_DATA_TEXTURE_FORMAT,
+            ..base_desc
+        };
+        let stencil_data_texture = texture_cache.get(
+            &render_device,
+            TextureDescriptor {
+                label: Some("stencil_data_output"),
+                ..data_desc
+            },
+        );
+
+        let rank_desc = TextureDescriptor {
+            format: STENCIL_RANK_TEXTURE_FORMAT,
+            ..base_desc
+        };
+        let stencil_rank_texture = texture_cache.get(
+            &render_device,
+            TextureDescriptor {
+                label: Some("stencil_rank_output"),
+                ..rank_desc
+            },
+        );
+
+        let stencil_depth_texture = texture_cache.get(
+            &render_device,
+            TextureDescriptor {
+                label: Some("stencil_depth_texture"),
+                format: STENCIL_DEPTH_FORMAT,
+                sample_count: msaa.samples(),
+                usage: TextureUsages::RENDER_ATTACHMENT,
+                ..base_desc
+            },
+        );
+
         let mut entity_commands = commands.entity(entity);
         entity_commands.insert(StencilTexture {
             texture: stencil_texture,
@@ -406,52 +1483,139 @@ fn prepare_outline_textures(
                     },
                 )),
             },
-        });
-
-        match settings.outline_type {
-            OutlineType::BoxBlur | OutlineType::GaussianBlur => {
-                let vertical_blur_texture = texture_cache.get(
+            data_texture: stencil_data_texture,
+            data_texture_sampled: match msaa.samples() {
+                1 => None,
+                _ => Some(texture_cache.get(
                     &render_device,
                     TextureDescriptor {
-                        label: Some("vertical_blur_output"),
-                        ..base_desc
+                        label: Some("stencil_data_texture_multisampled"),
+                        sample_count: msaa.samples(),
+                        ..data_desc
                     },
-                );
-                let horizontal_blur_texture = texture_cache.get(
+                )),
+            },
+            rank_texture: stencil_rank_texture,
+            rank_texture_sampled: match msaa.samples() {
+                1 => None,
+                _ => Some(texture_cache.get(
                     &render_device,
                     TextureDescriptor {
-                        label: Some("horizontal_blur_output"),
-                        ..base_desc
+                        label: Some("stencil_rank_texture_multisampled"),
+                        sample_count: msaa.samples(),
+                        ..rank_desc
                     },
-                );
+                )),
+            },
+            depth_texture: stencil_depth_texture,
+        });
 
-                entity_commands.insert(BlurredOutlineTextures {
-                    vertical_blur_texture,
-                    horizontal_blur_texture,
-                });
-            }
-            OutlineType::MaxFilter => {
-                let vertical_blur_texture = texture_cache.get(
-                    &render_device,
-                    TextureDescriptor {
-                        label: Some("vertical_blur_output"),
-                        ..base_desc
-                    },
-                );
-                let horizontal_blur_texture = texture_cache.get(
-                    &render_device,
-                    TextureDescriptor {
-                        label: Some("horizontal_blur_output"),
-                        ..base_desc
-                    },
-                );
+        // The blurred outlines can use a downsampled texture
+        let outline_texture_size = viewport.outline_texture_size(settings);
+        let outline_desc = TextureDescriptor {
+            size: Extent3d {
+                width: outline_texture_size.x,
+                height: outline_texture_size.y,
+                depth_or_array_layers: 1,
+            },
+            ..base_desc.clone()
+        };
 
-                entity_commands.insert(BlurredOutlineTextures {
-                    vertical_blur_texture,
-                    horizontal_blur_texture,
-                });
-            }
-            OutlineType::Jfa => todo!(),
+        // The final outline is always written to the horizontal_blur_texture
+        // so the combine pass doesn't need to know about the outline type
+        let two_passes = matches!(
+            settings.outline_type,
+            OutlineType::BoxBlur | OutlineType::GaussianBlur | OutlineType::MaxFilter
+        );
+        let vertical_blur_texture = two_passes.then(|| {
+            texture_cache.get(
+                &render_device,
+                TextureDescriptor {
+                    label: Some("vertical_blur_output"),
+                    ..outline_desc
+                },
+            )
+        });
+        let horizontal_blur_texture = texture_cache.get(
+            &render_device,
+            TextureDescriptor {
+                label: Some("horizontal_blur_output"),
+                ..outline_desc
+            },
+        );
+        let has_inner =
+            settings.side.has_inner() && settings.outline_type != OutlineType::EdgeDetect;
+        let inner_texture = has_inner.then(|| {
+            texture_cache.get(
+                &render_device,
+                TextureDescriptor {
+                    label: Some("inner_outline_output"),
+                    ..outline_desc
+                },
+            )
+        });
+
+        // The edge detection already draws lines between every entity
+        let group_edges = group_edges_layers.0.contains(&layer.layer)
+            && settings.outline_type != OutlineType::EdgeDetect;
+        // The interior lines are never downsampled
+        let has_lines = interior_lines_layers.0.contains(&layer.layer) || group_edges;
+        let interior_lines_texture = has_lines.then(|| {
+            texture_cache.get(
+                &render_device,
+                TextureDescriptor {
+                    label: Some("interior_lines_output"),
+                    ..base_desc
+                },
+            )
+        });
+
+        entity_commands.insert(BlurredOutlineTextures {
+            vertical_blur_texture,
+            horizontal_blur_texture,
+            inner_texture,
+            interior_lines_texture,
+            group_edges,
+        });
+
+        if settings.outline_type == OutlineType::MaxFilter {
+            let data = texture_cache.get(
+                &render_device,
+                TextureDescriptor {
+                    label: Some("max_filter_data"),
+                    ..data_desc
+                },
+            );
+            let rank = texture_cache.get(
+                &render_device,
+                TextureDescriptor {
+                    label: Some("max_filter_rank"),
+                    ..rank_desc
+                },
+            );
+            entity_commands.insert(MaxFilterTextures { data, rank });
+        }
+
+        if settings.outline_type == OutlineType::Jfa {
+            let jfa_desc = TextureDescriptor {
+                format: JFA_TEXTURE_FORMAT,
+                ..base_desc
+            };
+            let ping = texture_cache.get(
+                &render_device,
+                TextureDescriptor {
+                    label: Some("jfa_ping"),
+                    ..jfa_desc
+                },
+            );
+            let pong = texture_cache.get(
+                &render_device,
+                TextureDescriptor {
+                    label: Some("jfa_pong"),
+                    ..jfa_desc
+                },
+            );
+            entity_commands.insert(JfaTextures { ping, pong });
         }
     }
 }
@@ -1,7 +1,9 @@
+#import bevy_outline::kernel_shape
+
 struct MaxFilterSettings {
     size: f32,
     dims: vec2<f32>,
-    viewport: vec4<f32>,
+    kernel_shape: u32,
 };
 
 @group(0) @binding(0)
@@ -10,22 +12,116 @@ var input_texture: texture_2d<f32>;
 var stencil_sampler: sampler;
 @group(0) @binding(2)
 var<uniform> settings: MaxFilterSettings;
+@group(0) @binding(3)
+var stencil_data: texture_2d<f32>;
+// The rank of each pixel of the stencil, see overlap_rank in stencil_types.wgsl
+@group(0) @binding(4)
+var stencil_rank: texture_2d<f32>;
 
-fn get_sample_uv(uv: vec2<f32>) -> vec2<f32> {
-    return settings.viewport.xy + uv * settings.viewport.zw;
+// How far a pixel spreads horizontally once it's `dy` pixels away vertically
+fn horizontal_reach(width: f32, dy: f32) -> f32 {
+    if settings.kernel_shape == KERNEL_SQUARE {
+        return width;
+    }
+    if settings.kernel_shape == KERNEL_DIAMOND {
+        return width - dy;
+    }
+    return sqrt(max(width * width - dy * dy, 0.0));
 }
 
+// The highest tier wins, then the nearest pixel
+fn outranks(rank: vec2<f32>, other: vec2<f32>) -> bool {
+    return rank.x > other.x || (rank.x == other.x && rank.y > other.y);
+}
+
+struct FragmentOutput {
+    @location(0) color: vec4<f32>,
+#ifdef VERTICAL
+    // The horizontal pass reads them like the stencil data and rank
+    @location(1) data: vec4<f32>,
+    @location(2) rank: vec4<f32>,
+#endif
+};
+
+// The filter is separable, the vertical pass runs first then the horizontal pass spreads its result.
+// Each pixel keeps how far the outline it was taken from still reaches horizontally,
+// this gives the same result as a 2d kernel as long as the outlines that overlap have the same width.
 @fragment
-fn fragment(@location(0) uv: vec2<f32>) -> @location(0) vec4<f32> {
-    let sample_uv = get_sample_uv(uv);
-    var col = vec4(0.0);
+fn fragment(@location(0) uv: vec2<f32>) -> FragmentOutput {
+#ifdef VERTICAL
+    let direction = vec2(0.0, settings.dims.y);
+#else
+    let direction = vec2(settings.dims.x, 0.0);
+#endif
     let size = i32(settings.size);
-    for (var x = -size; x <= size; x++) {
-        for (var y = -size; y <= size; y++) {
-            let offset = vec2(f32(x), f32(y)) * settings.dims;
-            col = max(col, textureSample(input_texture, stencil_sampler, sample_uv + offset));
+    var out: FragmentOutput;
+
+#ifdef INNER
+    // The band inside the stencil only depends on the width of the pixel itself.
+    // The vertical pass uses the width of its own pixel, it's the same for every pixel of an entity.
+    let center_data = textureSample(stencil_data, stencil_sampler, uv);
+    let center_width = min(center_data.r, settings.size);
+    var inverted_coverage = 0.0;
+    // Vertical distance to the closest pixel outside the stencil
+    var nearest = settings.size + 1.0;
+    for (var i = -size; i <= size; i++) {
+        // Spread the inverted coverage of the stencil data, the color is added back in the combine pass
+        let coverage = textureSample(input_texture, stencil_sampler, uv + direction * f32(i)).a;
+#ifdef VERTICAL
+        if f32(abs(i)) <= center_width && coverage < 1.0 {
+            inverted_coverage = max(inverted_coverage, 1.0 - coverage);
+            nearest = min(nearest, f32(abs(i)));
+        }
+#else
+        let column_nearest = textureSample(stencil_data, stencil_sampler, uv + direction * f32(i)).g;
+        if kernel_distance(vec2(f32(i), column_nearest), settings.kernel_shape) <= center_width {
+            inverted_coverage = max(inverted_coverage, 1.0 - coverage);
+        }
+#endif
+    }
+#ifdef VERTICAL
+    // Stored as a coverage, the horizontal pass inverts it again like the stencil data.
+    // The green channel of the data is replaced by the distance to the closest pixel outside the stencil.
+    out.color = vec4(1.0 - inverted_coverage);
+    out.data = vec4(center_data.r, nearest, 0.0, center_data.a);
+    out.rank = vec4(0.0);
+#else
+    out.color = vec4(inverted_coverage);
+#endif
+#else
+    var col = vec4(0.0);
+    var best_rank = vec2(-1.0);
+    var best_data = vec4(0.0);
+    for (var i = -size; i <= size; i++) {
+        let offset = direction * f32(i);
+        let color = textureSample(input_texture, stencil_sampler, uv + offset);
+        let data = textureSample(stencil_data, stencil_sampler, uv + offset);
+        let rank = textureSample(stencil_rank, stencil_sampler, uv + offset).xy;
+        // Each pixel only spreads as far as the width of its own outline.
+        // The data written by the vertical pass already contains the horizontal reach.
+        let width = min(data.r, settings.size);
+#ifdef VERTICAL
+        let reach = vec4(horizontal_reach(width, f32(abs(i))), data.gba);
+#else
+        let reach = data;
+#endif
+        // Only the outline with the highest rank is kept, the colors of equal ranks are mixed
+        if data.a > 0.0 && f32(abs(i)) <= width {
+            if outranks(rank, best_rank) {
+                col = color;
+                best_rank = rank;
+                best_data = reach;
+            } else if all(rank == best_rank) {
+                col = max(col, color);
+                best_data = max(best_data, reach);
+            }
         }
     }
-    return col;
-    // return vec4(1.0);
+    out.color = col;
+#ifdef VERTICAL
+    out.data = best_data;
+    out.rank = vec4(best_rank, 0.0, 0.0);
+#endif
+#endif
+    return out;
 }
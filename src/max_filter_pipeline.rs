@@ -0,0 +1,109 @@
+use bevy::{
+    prelude::*,
+    render::{
+        render_resource::{
+            BindGroupLayout, BindGroupLayoutDescriptor, BindingType, BufferBindingType,
+            ColorTargetState, ColorWrites, RenderPipelineDescriptor, SamplerBindingType,
+            ShaderType, SpecializedRenderPipeline, TextureSampleType, TextureViewDimension,
+        },
+        renderer::RenderDevice,
+    },
+};
+
+use crate::{
+    bind_group_layout_entries,
+    blur_pipeline::BlurDirection,
+    stencil_phase::{STENCIL_DATA_TEXTURE_FORMAT, STENCIL_RANK_TEXTURE_FORMAT},
+    utils::{color_target, RenderPipelineDescriptorBuilder},
+    MaxFilterSettingsUniform, MAX_FILTER_SHADER_HANDLE,
+};
+
+#[derive(PartialEq, Eq, Hash, Clone, Copy)]
+pub struct MaxFilterPipelineKey {
+    pub direction: BlurDirection,
+    /// Spreads the inverted stencil
+    pub inner: bool,
+    /// The view is HDR, the colors are stored in a float texture
+    pub hdr: bool,
+}
+
+#[derive(Resource)]
+pub struct MaxFilterPipeline {
+    pub layout: BindGroupLayout,
+}
+
+impl FromWorld for MaxFilterPipeline {
+    fn from_world(world: &mut World) -> Self {
+        let render_device = world.resource::<RenderDevice>();
+
+        let texture = BindingType::Texture {
+            sample_type: TextureSampleType::Float { filterable: true },
+            view_dimension: TextureViewDimension::D2,
+            multisampled: false,
+        };
+
+        let layout = render_device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+            label: Some("max_filter_bind_group_layout"),
+            entries: &bind_group_layout_entries![
+                // input texture
+                0 => texture,
+                // sampler
+                1 => BindingType::Sampler(SamplerBindingType::Filtering),
+                // uniform
+                2 => BindingType::Buffer {
+                    ty: BufferBindingType::Uniform,
+                    has_dynamic_offset: true,
+                    min_binding_size: Some(MaxFilterSettingsUniform::min_size()),
+                },
+                // stencil data texture
+                3 => texture,
+                // stencil rank texture
+                4 => texture,
+            ],
+        });
+
+        MaxFilterPipeline { layout }
+    }
+}
+
+impl SpecializedRenderPipeline for MaxFilterPipeline {
+    type Key = MaxFilterPipelineKey;
+
+    fn specialize(&self, key: Self::Key) -> RenderPipelineDescriptor {
+        let mut shader_defs = vec![];
+
+        // The vertical pass runs first, it also writes the data and the rank read by the horizontal pass
+        let targets = match key.direction {
+            BlurDirection::Vertical => {
+                shader_defs.push("VERTICAL".into());
+                vec![
+                    color_target(None, key.hdr),
+                    ColorTargetState {
+                        format: STENCIL_DATA_TEXTURE_FORMAT,
+                        blend: None,
+                        write_mask: ColorWrites::ALL,
+                    },
+                    ColorTargetState {
+                        format: STENCIL_RANK_TEXTURE_FORMAT,
+                        blend: None,
+                        write_mask: ColorWrites::ALL,
+                    },
+                ]
+            }
+            BlurDirection::Horizontal => {
+                shader_defs.push("HORIZONTAL".into());
+                vec![color_target(None, key.hdr)]
+            }
+        };
+
+        if key.inner {
+            shader_defs.push("INNER".into());
+        }
+
+        RenderPipelineDescriptorBuilder::fullscreen()
+            .label(format!("{}_max_filter_pipeline", key.direction))
+            .layout(vec![self.layout.clone()])
+            .fragment(MAX_FILTER_SHADER_HANDLE, "fragment", &targets, &shader_defs)
+            .build()
+    }
+}
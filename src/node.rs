@@ -1,39 +1,135 @@
 use bevy::{
+    core_pipeline::prepass::ViewPrepassTextures,
     prelude::*,
     render::{
+        camera::ExtractedCamera,
         extract_component::{ComponentUniforms, DynamicUniformIndex},
+        globals::GlobalsBuffer,
         render_graph::{Node, RenderGraphContext, SlotInfo, SlotType},
         render_phase::RenderPhase,
         render_resource::{
             BindGroup, BindGroupDescriptor, BindingResource, LoadOp, Operations, PipelineCache,
-            RenderPassColorAttachment, RenderPassDescriptor, RenderPipeline,
+            RenderPassColorAttachment, RenderPassDepthStencilAttachment, RenderPassDescriptor,
+            RenderPipeline, TextureView,
         },
         renderer::{RenderContext, RenderDevice},
-        texture::CachedTexture,
-        view::ViewTarget,
+        texture::{CachedTexture, FallbackImageDepthCache, FallbackImageMsaaCache},
+        view::{ViewDepthTexture, ViewTarget},
     },
 };
 
 use crate::{
-    bind_group_entries, blur_pipeline::BlurPipeline, stencil_phase::MeshStencil, BlurPipelines,
-    BlurUniform, BlurredOutlineTextures, CombineSettingsUniform, MaxFilterSettingsUniform,
-    OutlineSettings, OutlineType, StencilTexture,
+    bind_group_entries,
+    blur_pipeline::BlurPipeline,
+    edge_detect_pipeline::EdgeDetectPipeline,
+    jfa::{jfa_steps, JfaFloodUniforms},
+    max_filter_pipeline::MaxFilterPipeline,
+    stencil_phase::{render_stencil_layer, MeshStencil, StencilPhaseItem},
+    stencil_phase_2d::Stencil2d,
+    BlurPipelines, BlurUniform, BlurredOutlineTextures, CombineSettingsUniform,
+    EdgeDetectPipelineId, EdgeDetectSettingsUniform, InteriorLinesPipelineId, JfaTextures,
+    MaxFilterPipelines, MaxFilterSettingsUniform, MaxFilterTextures, OutlineLayer, OutlineSettings,
+    OutlineType, StencilTexture, ViewOutlineLayer, ViewOutlineLayers,
 };
 
 use super::OutlineMeta;
 
+/// Render node for drawing the [`OutlineType::InvertedHull`] outlines.
+///
+/// The hulls are geometry drawn with the depth of the main pass, it must run right after it.
+pub struct OutlineHullNode {
+    query: QueryState<(
+        &'static ExtractedCamera,
+        &'static ViewTarget,
+        &'static RenderPhase<MeshStencil>,
+        &'static ViewOutlineLayers,
+        &'static ViewDepthTexture,
+    )>,
+    layer_query: QueryState<(&'static ViewOutlineLayer, &'static OutlineSettings)>,
+}
+
+impl OutlineHullNode {
+    pub const IN_VIEW: &'static str = "view";
+
+    pub fn new(world: &mut World) -> OutlineHullNode {
+        OutlineHullNode {
+            query: QueryState::new(world),
+            layer_query: QueryState::new(world),
+        }
+    }
+}
+
+impl Node for OutlineHullNode {
+    fn input(&self) -> Vec<SlotInfo> {
+        vec![SlotInfo::new(Self::IN_VIEW, SlotType::Entity)]
+    }
+
+    fn update(&mut self, world: &mut World) {
+        self.query.update_archetypes(world);
+        self.layer_query.update_archetypes(world);
+    }
+
+    fn run(
+        &self,
+        graph: &mut RenderGraphContext,
+        render_context: &mut RenderContext,
+        world: &World,
+    ) -> Result<(), bevy::render::render_graph::NodeRunError> {
+        let view_entity = graph.get_input_entity(Self::IN_VIEW)?;
+        let Ok((camera, view_target, stencil_phase, layers, depth)) = self.query.get_manual(world, view_entity) else {
+            return Ok(());
+        };
+
+        for layer_entity in layers.0.iter().copied() {
+            let Ok((layer, settings)) = self.layer_query.get_manual(world, layer_entity) else {
+                continue;
+            };
+            if settings.outline_type == OutlineType::InvertedHull
+                && has_layer(stencil_phase, layer.layer)
+            {
+                draw_hulls(
+                    render_context,
+                    world,
+                    stencil_phase,
+                    layer.layer,
+                    view_entity,
+                    camera,
+                    view_target,
+                    depth,
+                );
+            }
+        }
+
+        Ok(())
+    }
+}
+
 /// Render node for drawing blurred outlines of selected meshes
 pub struct OutlineNode {
     query: QueryState<(
+        &'static ExtractedCamera,
         &'static ViewTarget,
-        &'static RenderPhase<MeshStencil>,
+        Option<&'static RenderPhase<MeshStencil>>,
+        Option<&'static RenderPhase<Stencil2d>>,
+        &'static ViewOutlineLayers,
+        Option<&'static ViewPrepassTextures>,
+    )>,
+    layer_query: QueryState<(
+        &'static ViewOutlineLayer,
         &'static BlurredOutlineTextures,
         &'static StencilTexture,
         &'static DynamicUniformIndex<BlurUniform>,
         &'static DynamicUniformIndex<CombineSettingsUniform>,
         &'static DynamicUniformIndex<MaxFilterSettingsUniform>,
+        &'static DynamicUniformIndex<EdgeDetectSettingsUniform>,
         Option<&'static BlurPipelines>,
+        Option<&'static MaxFilterPipelines>,
+        Option<&'static MaxFilterTextures>,
+        Option<&'static JfaTextures>,
+        Option<&'static EdgeDetectPipelineId>,
+        Option<&'static InteriorLinesPipelineId>,
         &'static OutlineSettings,
+        &'static MaxFilterSettingsUniform,
     )>,
 }
 
@@ -43,6 +139,7 @@ impl OutlineNode {
     pub fn new(world: &mut World) -> OutlineNode {
         OutlineNode {
             query: QueryState::new(world),
+            layer_query: QueryState::new(world),
         }
     }
 }
@@ -54,6 +151,7 @@ impl Node for OutlineNode {
 
     fn update(&mut self, world: &mut World) {
         self.query.update_archetypes(world);
+        self.layer_query.update_archetypes(world);
     }
 
     fn run(
@@ -63,17 +161,7 @@ impl Node for OutlineNode {
         world: &World,
     ) -> Result<(), bevy::render::render_graph::NodeRunError> {
         let view_entity = graph.get_input_entity(Self::IN_VIEW)?;
-        let Ok((
-            view_target,
-            stencil_phase,
-            blur_textures,
-            stencil_texture,
-            blur_uniform_index,
-            intensity_uniform_index,
-            max_filter_settings_uniform_index,
-            blur_pipelines,
-            settings,
-        )) = self.query.get_manual(world, view_entity) else {
+        let Ok((camera, view_target, stencil_phase, stencil_phase_2d, layers, prepass_textures)) = self.query.get_manual(world, view_entity) else {
             return Ok(());
         };
 
@@ -81,6 +169,8 @@ impl Node for OutlineNode {
         let pipeline_cache = world.resource::<PipelineCache>();
         let render_device = world.resource::<RenderDevice>();
         let blur_pipeline = world.resource::<BlurPipeline>();
+        let max_filter_pipeline = world.resource::<MaxFilterPipeline>();
+        let edge_detect_pipeline = world.resource::<EdgeDetectPipeline>();
         let Some(blur_uniforms) = world.resource::<ComponentUniforms<BlurUniform>>().binding() else {
             return Ok(());
         };
@@ -90,171 +180,557 @@ impl Node for OutlineNode {
         let Some(max_filter_settings_uniforms) = world.resource::<ComponentUniforms<MaxFilterSettingsUniform>>().binding() else {
             return Ok(());
         };
+        let Some(edge_detect_settings_uniforms) = world.resource::<ComponentUniforms<EdgeDetectSettingsUniform>>().binding() else {
+            return Ok(());
+        };
 
-        let (Some(combine_pipeline), Some(max_filter_pipeline)) = (
-            pipeline_cache.get_render_pipeline(pipelines.combine_pipeline),
-            pipeline_cache.get_render_pipeline(pipelines.max_filter_pipeline)
-        ) else {
+        let Some(globals) = world.resource::<GlobalsBuffer>().buffer.binding() else {
             return Ok(());
         };
 
         // General algorithm:
-        // 1. Generate a stencil buffer of all the meshes with an outline component
-        // 2. Vertical blur on the stencil buffer
+        // 1. Generate a stencil buffer of all the meshes and sprites with an outline component
+        // 2. Vertical blur on the stencil buffer, optionally downsampled
         // 3. Horizontal blur on the vertical blur buffer
-        // 4. Combine the final texture with the view_target
-
-        // Draw stencil of all the entities with outlines
-        draw_stencil(
-            stencil_texture,
-            render_context,
-            world,
-            stencil_phase,
-            view_entity,
-        );
-
-        // TODO figure out how to downsample
-
-        match settings.outline_type {
-            OutlineType::BoxBlur | OutlineType::GaussianBlur => {
-                let Some(blur_pipelines) = blur_pipelines else {
-                    return Ok(());
-                };
+        //    The max filter is separable too and follows the same steps
+        //    Inner outlines repeat steps 2 and 3 on the inverted stencil
+        //    Edge detection replaces steps 2 and 3 with a Sobel filter over the whole view
+        // 4. Combine the final texture with the view_target, this upsamples the blur
+        // Inverted hulls don't have any texture, they are drawn by the OutlineHullNode
 
-                let (Some(vertical_blur_pipeline), Some(horizontal_blur_pipeline)) = (
-                    pipeline_cache.get_render_pipeline(blur_pipelines.vertical_blur_pipeline_id),
-                    pipeline_cache.get_render_pipeline(blur_pipelines.horizontal_blur_pipeline_id),
-                ) else {
-                    return Ok(());
-                };
+        // Every layer is drawn independently, the layers without any outline are skipped
+        'layers: for layer_entity in layers.0.iter().copied() {
+            let Ok((
+                layer,
+                blur_textures,
+                stencil_texture,
+                blur_uniform_index,
+                intensity_uniform_index,
+                max_filter_settings_uniform_index,
+                edge_detect_settings_uniform_index,
+                blur_pipelines,
+                max_filter_pipelines,
+                max_filter_textures,
+                jfa_textures,
+                edge_detect_pipeline_id,
+                interior_lines_pipeline_id,
+                settings,
+                max_filter_settings,
+            )) = self.layer_query.get_manual(world, layer_entity) else {
+                continue;
+            };
+            // Edge detection draws lines on the whole scene, it doesn't need any outline
+            let has_outlines = matches!(stencil_phase, Some(phase) if has_layer(phase, layer.layer))
+                || matches!(stencil_phase_2d, Some(phase) if has_layer(phase, layer.layer))
+                || settings.outline_type == OutlineType::EdgeDetect;
+            if !has_outlines {
+                continue;
+            }
 
-                // TODO since only the texture changes, we should have a separate bind group for it
-                // This means we could also reuse parts of it for both direction
-                let blur_bind_group = |label, texture: &CachedTexture| {
-                    render_device.create_bind_group(&BindGroupDescriptor {
-                        label: Some(&format!("{label}_bind_group")),
-                        layout: &blur_pipeline.layout,
-                        entries: &bind_group_entries![
-                            0 => BindingResource::TextureView(&texture.default_view),
-                            1 => BindingResource::Sampler(&pipelines.sampler),
-                            2 => blur_uniforms.clone(),
-                        ],
-                    })
-                };
-                blur_pass(
-                    render_context,
-                    vertical_blur_pipeline,
-                    blur_bind_group("vertical_blur", &stencil_texture.texture),
-                    blur_uniform_index,
-                    &blur_textures.vertical_blur_texture,
-                );
+            // Draw stencil of all the entities with outlines in this layer
+            draw_stencil(
+                stencil_texture,
+                render_context,
+                world,
+                stencil_phase,
+                stencil_phase_2d,
+                layer.layer,
+                view_entity,
+            );
 
-                let horizontal_bind_group =
-                    blur_bind_group("horizontal_blur", &blur_textures.vertical_blur_texture);
-                blur_pass(
-                    render_context,
-                    horizontal_blur_pipeline,
-                    horizontal_bind_group.clone(),
-                    blur_uniform_index,
-                    &blur_textures.horizontal_blur_texture,
-                );
+            // Inner outlines run the same algorithm on the inverted stencil into their own texture
+            let outputs = [
+                (
+                    false,
+                    settings
+                        .side
+                        .has_outer()
+                        .then_some(&blur_textures.horizontal_blur_texture),
+                ),
+                (true, blur_textures.inner_texture.as_ref()),
+            ];
+            for (inner, output_texture) in outputs {
+                let Some(output_texture) = output_texture else {
+                    continue;
+                };
+                // Inner outlines only need the coverage, it's stored in the alpha of the stencil data
+                let input_texture = match inner {
+                    true => &stencil_texture.data_texture,
+                    false => &stencil_texture.texture,
+                };
 
-                if let OutlineType::GaussianBlur = settings.outline_type {
-                    // This essentially re-runs the blur on the already blurred texture.
-                    // This makes it possible to have wider outlines.
-                    // Using only a single step generates a lot of artifacts when using large sizes.
+                match settings.outline_type {
+                    OutlineType::BoxBlur | OutlineType::GaussianBlur => {
+                        let (Some(blur_pipelines), Some(vertical_blur_texture)) = (blur_pipelines, &blur_textures.vertical_blur_texture) else {
+                            continue 'layers;
+                        };
 
-                    let vertical_bind_group =
-                        blur_bind_group("vertical_blur", &blur_textures.horizontal_blur_texture);
+                        let first_vertical_blur_pipeline_id = match inner {
+                            true => blur_pipelines.inner_vertical_blur_pipeline_id,
+                            false => blur_pipelines.vertical_blur_pipeline_id,
+                        };
+                        let (
+                            Some(first_vertical_blur_pipeline),
+                            Some(vertical_blur_pipeline),
+                            Some(horizontal_blur_pipeline),
+                        ) = (
+                            pipeline_cache.get_render_pipeline(first_vertical_blur_pipeline_id),
+                            pipeline_cache.get_render_pipeline(blur_pipelines.vertical_blur_pipeline_id),
+                            pipeline_cache.get_render_pipeline(blur_pipelines.horizontal_blur_pipeline_id),
+                        ) else {
+                            continue 'layers;
+                        };
 
-                    for _ in 0..3 {
+                        // TODO since only the texture changes, we should have a separate bind group for it
+                        // This means we could also reuse parts of it for both direction
+                        let blur_bind_group = |label, texture: &CachedTexture| {
+                            render_device.create_bind_group(&BindGroupDescriptor {
+                                label: Some(&format!("{label}_bind_group")),
+                                layout: &blur_pipeline.layout,
+                                entries: &bind_group_entries![
+                                    0 => BindingResource::TextureView(&texture.default_view),
+                                    1 => BindingResource::Sampler(&pipelines.sampler),
+                                    2 => blur_uniforms.clone(),
+                                ],
+                            })
+                        };
                         blur_pass(
                             render_context,
-                            vertical_blur_pipeline,
-                            vertical_bind_group.clone(),
+                            first_vertical_blur_pipeline,
+                            blur_bind_group("vertical_blur", input_texture),
                             blur_uniform_index,
-                            &blur_textures.vertical_blur_texture,
+                            vertical_blur_texture,
+                        );
+
+                        let horizontal_bind_group = blur_bind_group(
+                            "horizontal_blur",
+                            vertical_blur_texture,
                         );
                         blur_pass(
                             render_context,
                             horizontal_blur_pipeline,
                             horizontal_bind_group.clone(),
                             blur_uniform_index,
-                            &blur_textures.horizontal_blur_texture,
+                            output_texture,
+                        );
+
+                        if let OutlineType::GaussianBlur = settings.outline_type {
+                            // This essentially re-runs the blur on the already blurred texture.
+                            // This makes it possible to have wider outlines.
+                            // Using only a single step generates a lot of artifacts when using large sizes.
+
+                            let vertical_bind_group =
+                                blur_bind_group("vertical_blur", output_texture);
+
+                            for _ in 0..3 {
+                                blur_pass(
+                                    render_context,
+                                    vertical_blur_pipeline,
+                                    vertical_bind_group.clone(),
+                                    blur_uniform_index,
+                                    vertical_blur_texture,
+                                );
+                                blur_pass(
+                                    render_context,
+                                    horizontal_blur_pipeline,
+                                    horizontal_bind_group.clone(),
+                                    blur_uniform_index,
+                                    output_texture,
+                                );
+                            }
+                        }
+                    }
+                    OutlineType::MaxFilter => {
+                        let (Some(max_filter_pipelines), Some(max_filter_textures), Some(vertical_blur_texture)) = (max_filter_pipelines, max_filter_textures, &blur_textures.vertical_blur_texture) else {
+                            continue 'layers;
+                        };
+                        let (vertical_pipeline_id, horizontal_pipeline_id) = match inner {
+                            true => (
+                                max_filter_pipelines.inner_vertical_pipeline_id,
+                                max_filter_pipelines.inner_horizontal_pipeline_id,
+                            ),
+                            false => (
+                                max_filter_pipelines.vertical_pipeline_id,
+                                max_filter_pipelines.horizontal_pipeline_id,
+                            ),
+                        };
+                        let (Some(vertical_pipeline), Some(horizontal_pipeline)) = (
+                            pipeline_cache.get_render_pipeline(vertical_pipeline_id),
+                            pipeline_cache.get_render_pipeline(horizontal_pipeline_id),
+                        ) else {
+                            continue 'layers;
+                        };
+
+                        // The input texture, then the data and the rank of each of its pixels
+                        let max_filter_bind_group =
+                            |label, [texture, data, rank]: [&CachedTexture; 3]| {
+                                render_device.create_bind_group(&BindGroupDescriptor {
+                                    label: Some(&format!("{label}_bind_group")),
+                                    layout: &max_filter_pipeline.layout,
+                                    entries: &bind_group_entries![
+                                        0 => BindingResource::TextureView(&texture.default_view),
+                                        1 => BindingResource::Sampler(&pipelines.sampler),
+                                        2 => max_filter_settings_uniforms.clone(),
+                                        3 => BindingResource::TextureView(&data.default_view),
+                                        4 => BindingResource::TextureView(&rank.default_view),
+                                    ],
+                                })
+                            };
+
+                        // The vertical pass also writes the data and the rank of the outline it kept
+                        max_filter_pass(
+                            render_context,
+                            &[
+                                vertical_blur_texture,
+                                &max_filter_textures.data,
+                                &max_filter_textures.rank,
+                            ],
+                            vertical_pipeline,
+                            max_filter_bind_group(
+                                "vertical_max_filter",
+                                [
+                                    input_texture,
+                                    &stencil_texture.data_texture,
+                                    &stencil_texture.rank_texture,
+                                ],
+                            ),
+                            max_filter_settings_uniform_index,
+                        );
+                        max_filter_pass(
+                            render_context,
+                            &[output_texture],
+                            horizontal_pipeline,
+                            max_filter_bind_group(
+                                "horizontal_max_filter",
+                                [
+                                    vertical_blur_texture,
+                                    &max_filter_textures.data,
+                                    &max_filter_textures.rank,
+                                ],
+                            ),
+                            max_filter_settings_uniform_index,
                         );
                     }
+                    OutlineType::Jfa => {
+                        let Some(jfa_textures) = jfa_textures else {
+                            continue 'layers;
+                        };
+                        let (seed_pipeline_id, outline_pipeline_id) = match inner {
+                            true => (
+                                pipelines.jfa_inner_seed_pipeline,
+                                pipelines.jfa_inner_outline_pipeline,
+                            ),
+                            false => (pipelines.jfa_seed_pipeline, pipelines.jfa_outline_pipeline),
+                        };
+                        let outline_pipeline_id = outline_pipeline_id.get(view_target.is_hdr());
+                        let (Some(seed_pipeline), Some(flood_pipeline), Some(outline_pipeline)) = (
+                            pipeline_cache.get_render_pipeline(seed_pipeline_id),
+                            pipeline_cache.get_render_pipeline(pipelines.jfa_flood_pipeline),
+                            pipeline_cache.get_render_pipeline(outline_pipeline_id),
+                        ) else {
+                            continue 'layers;
+                        };
+                        let flood_uniforms = world.resource::<JfaFloodUniforms>();
+                        let Some(flood_uniforms_binding) = flood_uniforms.buffer.binding() else {
+                            continue 'layers;
+                        };
+
+                        // Every pixel covered by the stencil becomes a seed
+                        let seed_bind_group = render_device.create_bind_group(&BindGroupDescriptor {
+                            label: Some("jfa_seed_bind_group"),
+                            layout: &pipelines.jfa_seed_bind_group_layout,
+                            entries: &bind_group_entries![
+                                1 => BindingResource::TextureView(&stencil_texture.data_texture.default_view),
+                                2 => BindingResource::Sampler(&pipelines.sampler),
+                                3 => max_filter_settings_uniforms.clone(),
+                            ],
+                        });
+                        fullscreen_pass(
+                            render_context,
+                            "jfa_seed_pass",
+                            &jfa_textures.ping,
+                            seed_pipeline,
+                            &seed_bind_group,
+                            &[max_filter_settings_uniform_index.index()],
+                        );
+
+                        // Flood the seeds with decreasing steps, ping-ponging between both textures
+                        let mut src = &jfa_textures.ping;
+                        let mut dst = &jfa_textures.pong;
+                        // The widths are in pixels once they are in the stencil
+                        for step in jfa_steps(max_filter_settings.size) {
+                            let flood_bind_group = render_device.create_bind_group(&BindGroupDescriptor {
+                                label: Some("jfa_flood_bind_group"),
+                                layout: &pipelines.jfa_flood_bind_group_layout,
+                                entries: &bind_group_entries![
+                                    0 => BindingResource::TextureView(&src.default_view),
+                                    3 => max_filter_settings_uniforms.clone(),
+                                    4 => flood_uniforms_binding.clone(),
+                                    5 => BindingResource::TextureView(&stencil_texture.data_texture.default_view),
+                                    7 => BindingResource::TextureView(&stencil_texture.rank_texture.default_view),
+                                ],
+                            });
+                            fullscreen_pass(
+                                render_context,
+                                "jfa_flood_pass",
+                                dst,
+                                flood_pipeline,
+                                &flood_bind_group,
+                                &[
+                                    max_filter_settings_uniform_index.index(),
+                                    flood_uniforms.offset(step),
+                                ],
+                            );
+                            std::mem::swap(&mut src, &mut dst);
+                        }
+
+                        // Threshold the distance to the closest seed
+                        let outline_bind_group = render_device.create_bind_group(&BindGroupDescriptor {
+                            label: Some("jfa_outline_bind_group"),
+                            layout: &pipelines.jfa_outline_bind_group_layout,
+                            entries: &bind_group_entries![
+                                0 => BindingResource::TextureView(&src.default_view),
+                                1 => BindingResource::TextureView(&stencil_texture.texture.default_view),
+                                2 => BindingResource::Sampler(&pipelines.sampler),
+                                3 => max_filter_settings_uniforms.clone(),
+                                5 => BindingResource::TextureView(&stencil_texture.data_texture.default_view),
+                                6 => globals.clone(),
+                            ],
+                        });
+                        fullscreen_pass(
+                            render_context,
+                            "jfa_outline_pass",
+                            output_texture,
+                            outline_pipeline,
+                            &outline_bind_group,
+                            &[max_filter_settings_uniform_index.index()],
+                        );
+                    }
+                    OutlineType::EdgeDetect => {
+                        let Some(edge_detect_pipeline_id) = edge_detect_pipeline_id else {
+                            continue 'layers;
+                        };
+                        let Some(pipeline) = pipeline_cache.get_render_pipeline(edge_detect_pipeline_id.0) else {
+                            continue 'layers;
+                        };
+                        let Some((depth_view, normal_view)) = prepass_views(world, prepass_textures) else {
+                            continue 'layers;
+                        };
+
+                        // The stencil rank isn't used, any texture can be bound
+                        let bind_group = render_device.create_bind_group(&BindGroupDescriptor {
+                            label: Some("edge_detect_bind_group"),
+                            layout: edge_detect_pipeline.layout(world),
+                            entries: &bind_group_entries![
+                                0 => BindingResource::TextureView(view_target.main_texture()),
+                                1 => BindingResource::TextureView(depth_view),
+                                2 => BindingResource::TextureView(normal_view),
+                                3 => edge_detect_settings_uniforms.clone(),
+                                4 => BindingResource::TextureView(&stencil_texture.rank_texture.default_view),
+                            ],
+                        });
+                        fullscreen_pass(
+                            render_context,
+                            "edge_detect_pass",
+                            output_texture,
+                            pipeline,
+                            &bind_group,
+                            &[edge_detect_settings_uniform_index.index()],
+                        );
+                    }
+                    OutlineType::InvertedHull => continue 'layers,
                 }
             }
-            OutlineType::MaxFilter => {
-                let max_filter_bind_group = render_device.create_bind_group(&BindGroupDescriptor {
-                    label: Some("max_filter_bind_group"),
-                    layout: &pipelines.max_filter_bind_group_layout,
-                    entries: &bind_group_entries![
-                        0 => BindingResource::TextureView(&stencil_texture.texture.default_view),
-                        1 => BindingResource::Sampler(&pipelines.sampler),
-                        2 => max_filter_settings_uniforms.clone(),
-                    ],
-                });
-                max_filter_pass(
-                    render_context,
-                    &blur_textures.horizontal_blur_texture,
-                    max_filter_pipeline,
-                    max_filter_bind_group,
-                    max_filter_settings_uniform_index,
-                );
+
+            // Lines where the depth or the normals change inside the outlines that have them
+            let interior_lines_texture = match (
+                &blur_textures.interior_lines_texture,
+                interior_lines_pipeline_id
+                    .and_then(|pipeline_id| pipeline_cache.get_render_pipeline(pipeline_id.0)),
+                prepass_views(world, prepass_textures),
+            ) {
+                (Some(texture), Some(pipeline), Some((depth_view, normal_view))) => {
+                    let bind_group = render_device.create_bind_group(&BindGroupDescriptor {
+                        label: Some("interior_lines_bind_group"),
+                        layout: edge_detect_pipeline.layout(world),
+                        entries: &bind_group_entries![
+                            0 => BindingResource::TextureView(&stencil_texture.texture.default_view),
+                            1 => BindingResource::TextureView(depth_view),
+                            2 => BindingResource::TextureView(normal_view),
+                            3 => edge_detect_settings_uniforms.clone(),
+                            4 => BindingResource::TextureView(&stencil_texture.rank_texture.default_view),
+                        ],
+                    });
+                    fullscreen_pass(
+                        render_context,
+                        "interior_lines_pass",
+                        texture,
+                        pipeline,
+                        &bind_group,
+                        &[edge_detect_settings_uniform_index.index()],
+                    );
+                    Some(texture)
+                }
+                _ => None,
+            };
+
+            // Lines where an outline of another group is in front, blended over the interior lines
+            let group_edges_pipeline = pipeline_cache
+                .get_render_pipeline(pipelines.group_edges_pipeline.get(view_target.is_hdr()));
+            let interior_lines_texture = match (
+                &blur_textures.interior_lines_texture,
+                group_edges_pipeline.filter(|_| blur_textures.group_edges),
+            ) {
+                (Some(texture), Some(pipeline)) => {
+                    let bind_group = render_device.create_bind_group(&BindGroupDescriptor {
+                        label: Some("group_edges_bind_group"),
+                        layout: &pipelines.group_edges_bind_group_layout,
+                        entries: &bind_group_entries![
+                            0 => BindingResource::TextureView(&stencil_texture.texture.default_view),
+                            1 => BindingResource::TextureView(&stencil_texture.data_texture.default_view),
+                            2 => BindingResource::TextureView(&stencil_texture.rank_texture.default_view),
+                            3 => max_filter_settings_uniforms.clone(),
+                        ],
+                    });
+                    fullscreen_pass_with_clear(
+                        render_context,
+                        "group_edges_pass",
+                        texture,
+                        interior_lines_texture.is_none(),
+                        pipeline,
+                        &bind_group,
+                        &[max_filter_settings_uniform_index.index()],
+                    );
+                    Some(texture)
+                }
+                _ => interior_lines_texture,
+            };
+
+            // final combine pass
+            let combine_pipeline_id = match interior_lines_texture {
+                Some(_) => pipelines.combine_interior_lines_pipeline,
+                None => pipelines.combine_pipeline,
             }
-            OutlineType::Jfa => todo!(),
+            .get(view_target.is_hdr());
+            let Some(combine_pipeline) = pipeline_cache.get_render_pipeline(combine_pipeline_id) else {
+                continue;
+            };
+            // Any texture can be bound when there's no inner outline or interior lines, it isn't used
+            let inner_texture = blur_textures
+                .inner_texture
+                .as_ref()
+                .unwrap_or(&blur_textures.horizontal_blur_texture);
+            let interior_lines_texture =
+                interior_lines_texture.unwrap_or(&blur_textures.horizontal_blur_texture);
+            let combine_bind_group = render_device.create_bind_group(&BindGroupDescriptor {
+                label: Some("outline_combine_bind_group"),
+                layout: &pipelines.combine_bind_group_layout,
+                entries: &bind_group_entries![
+                    0 => BindingResource::Sampler(&pipelines.sampler),
+                    1 => BindingResource::TextureView(&stencil_texture.texture.default_view),
+                    2 => BindingResource::TextureView(&blur_textures.horizontal_blur_texture.default_view),
+                    3 => combine_settings_uniforms.clone(),
+                    4 => BindingResource::TextureView(&inner_texture.default_view),
+                    5 => BindingResource::TextureView(&stencil_texture.data_texture.default_view),
+                    6 => BindingResource::TextureView(&interior_lines_texture.default_view),
+                ],
+            });
+            combine_pass(
+                render_context,
+                combine_pipeline,
+                combine_bind_group,
+                camera,
+                view_target,
+                intensity_uniform_index,
+            );
         }
 
-        // final combine pass
-        let combine_bind_group = render_device.create_bind_group(&BindGroupDescriptor {
-            label: Some("outline_combine_bind_group"),
-            layout: &pipelines.combine_bind_group_layout,
-            entries: &bind_group_entries![
-                0 => BindingResource::Sampler(&pipelines.sampler),
-                1 => BindingResource::TextureView(&stencil_texture.texture.default_view),
-                2 => BindingResource::TextureView(&blur_textures.horizontal_blur_texture.default_view),
-                3 => combine_settings_uniforms.clone(),
-            ],
-        });
-        combine_pass(
-            render_context,
-            combine_pipeline,
-            combine_bind_group,
-            view_target,
-            intensity_uniform_index,
-        );
-
         Ok(())
     }
 }
 
 fn max_filter_pass(
     render_context: &mut RenderContext,
-    texture: &CachedTexture,
+    textures: &[&CachedTexture],
     max_filter_pipeline: &RenderPipeline,
     max_filter_bind_group: BindGroup,
     max_filter_settings_uniform_index: &DynamicUniformIndex<MaxFilterSettingsUniform>,
 ) {
+    let color_attachments: Vec<_> = textures
+        .iter()
+        .map(|texture| {
+            Some(RenderPassColorAttachment {
+                view: &texture.default_view,
+                resolve_target: None,
+                ops: Operations {
+                    load: LoadOp::Clear(Color::NONE.into()),
+                    store: true,
+                },
+            })
+        })
+        .collect();
     let mut pass = render_context.begin_tracked_render_pass(RenderPassDescriptor {
         label: Some("max_filter_pass"),
+        color_attachments: &color_attachments,
+        depth_stencil_attachment: None,
+    });
+
+    pass.set_render_pipeline(max_filter_pipeline);
+    pass.set_bind_group(
+        0,
+        &max_filter_bind_group,
+        &[max_filter_settings_uniform_index.index()],
+    );
+    pass.draw(0..3, 0..1);
+}
+
+fn fullscreen_pass(
+    render_context: &mut RenderContext,
+    label: &str,
+    texture: &CachedTexture,
+    pipeline: &RenderPipeline,
+    bind_group: &BindGroup,
+    dynamic_offsets: &[u32],
+) {
+    fullscreen_pass_with_clear(
+        render_context,
+        label,
+        texture,
+        true,
+        pipeline,
+        bind_group,
+        dynamic_offsets,
+    );
+}
+
+/// A fullscreen pass that draws over the current content of the texture when `clear` is false
+fn fullscreen_pass_with_clear(
+    render_context: &mut RenderContext,
+    label: &str,
+    texture: &CachedTexture,
+    clear: bool,
+    pipeline: &RenderPipeline,
+    bind_group: &BindGroup,
+    dynamic_offsets: &[u32],
+) {
+    let mut pass = render_context.begin_tracked_render_pass(RenderPassDescriptor {
+        label: Some(label),
         color_attachments: &[Some(RenderPassColorAttachment {
             view: &texture.default_view,
             resolve_target: None,
             ops: Operations {
-                load: LoadOp::Clear(Color::NONE.into()),
+                load: match clear {
+                    true => LoadOp::Clear(Color::NONE.into()),
+                    false => LoadOp::Load,
+                },
                 store: true,
             },
         })],
         depth_stencil_attachment: None,
     });
 
-    pass.set_render_pipeline(max_filter_pipeline);
-    pass.set_bind_group(
-        0,
-        &max_filter_bind_group,
-        &[max_filter_settings_uniform_index.index()],
-    );
+    pass.set_render_pipeline(pipeline);
+    pass.set_bind_group(0, bind_group, dynamic_offsets);
     pass.draw(0..3, 0..1);
 }
 
@@ -262,15 +738,85 @@ fn draw_stencil(
     stencil_texture: &StencilTexture,
     render_context: &mut RenderContext,
     world: &World,
-    stencil_phase: &RenderPhase<MeshStencil>,
+    stencil_phase: Option<&RenderPhase<MeshStencil>>,
+    stencil_phase_2d: Option<&RenderPhase<Stencil2d>>,
+    layer: OutlineLayer,
     view_entity: Entity,
 ) {
     let mut pass = render_context.begin_tracked_render_pass(RenderPassDescriptor {
         label: Some("outline_stencil_pass"),
-        color_attachments: &[stencil_texture.get_color_attachment()],
-        depth_stencil_attachment: None,
+        color_attachments: &stencil_texture.get_color_attachments(),
+        depth_stencil_attachment: Some(stencil_texture.get_depth_stencil_attachment()),
     });
-    stencil_phase.render(&mut pass, world, view_entity);
+    if let Some(stencil_phase) = stencil_phase {
+        render_stencil_layer(stencil_phase, layer, &mut pass, world, view_entity);
+    }
+    if let Some(stencil_phase_2d) = stencil_phase_2d {
+        render_stencil_layer(stencil_phase_2d, layer, &mut pass, world, view_entity);
+    }
+}
+
+fn draw_hulls(
+    render_context: &mut RenderContext,
+    world: &World,
+    stencil_phase: &RenderPhase<MeshStencil>,
+    layer: OutlineLayer,
+    view_entity: Entity,
+    camera: &ExtractedCamera,
+    view_target: &ViewTarget,
+    depth: &ViewDepthTexture,
+) {
+    let mut pass = render_context.begin_tracked_render_pass(RenderPassDescriptor {
+        label: Some("outline_hull_pass"),
+        color_attachments: &[Some(view_target.get_color_attachment(Operations {
+            load: LoadOp::Load,
+            store: true,
+        }))],
+        depth_stencil_attachment: Some(RenderPassDepthStencilAttachment {
+            view: &depth.view,
+            depth_ops: Some(Operations {
+                load: LoadOp::Load,
+                store: true,
+            }),
+            stencil_ops: None,
+        }),
+    });
+    // The view target covers the whole render target, the hulls are only drawn inside the viewport
+    if let Some(viewport) = camera.viewport.as_ref() {
+        pass.set_camera_viewport(viewport);
+    }
+    render_stencil_layer(stencil_phase, layer, &mut pass, world, view_entity);
+}
+
+/// The depth and normal prepass textures of the view, or the fallback textures when the view doesn't have them
+fn prepass_views<'w>(
+    world: &'w World,
+    prepass_textures: Option<&'w ViewPrepassTextures>,
+) -> Option<(&'w TextureView, &'w TextureView)> {
+    let samples = world.resource::<Msaa>().samples();
+    let depth_view = match prepass_textures.and_then(|textures| textures.depth.as_ref()) {
+        Some(texture) => &texture.default_view,
+        None => {
+            &world
+                .resource::<FallbackImageDepthCache>()
+                .get(&samples)?
+                .texture_view
+        }
+    };
+    let normal_view = match prepass_textures.and_then(|textures| textures.normal.as_ref()) {
+        Some(texture) => &texture.default_view,
+        None => {
+            &world
+                .resource::<FallbackImageMsaaCache>()
+                .get(&samples)?
+                .texture_view
+        }
+    };
+    Some((depth_view, normal_view))
+}
+
+fn has_layer<I: StencilPhaseItem>(phase: &RenderPhase<I>, layer: OutlineLayer) -> bool {
+    phase.items.iter().any(|item| item.layer() == layer)
 }
 
 fn blur_pass(
@@ -302,6 +848,7 @@ fn combine_pass(
     render_context: &mut RenderContext,
     pipeline: &RenderPipeline,
     bind_group: BindGroup,
+    camera: &ExtractedCamera,
     view_target: &ViewTarget,
     intensity_uniform_index: &DynamicUniformIndex<CombineSettingsUniform>,
 ) {
@@ -316,6 +863,10 @@ fn combine_pass(
         depth_stencil_attachment: None,
     });
 
+    // The outline textures only cover the viewport of the view target
+    if let Some(viewport) = camera.viewport.as_ref() {
+        pass.set_camera_viewport(viewport);
+    }
     pass.set_render_pipeline(pipeline);
     pass.set_bind_group(0, &bind_group, &[intensity_uniform_index.index()]);
     pass.draw(0..3, 0..1);
@@ -0,0 +1,316 @@
+use bevy::prelude::*;
+use bevy_mod_picking::{Hover, PickingSystem, Selection};
+
+use crate::{transition::OutlineTransitionState, Outline, OutlineTransition, OutlineTree};
+
+/// Outlines the entities of `bevy_mod_picking` while they are hovered, pressed or selected.
+///
+/// Add it next to the picking plugins, the entities need a `PickableBundle`.
+/// Several entities can be selected at once by holding left control.
+/// The outlines also cover the meshes of the descendants of the entities, see [`OutlineTree`].
+/// The [`Outline`] an entity had before is restored once it isn't hovered, pressed or selected anymore.
+/// An [`Outline`] inserted in the meantime is restored instead.
+#[derive(Default)]
+pub struct OutlineSelectionPlugin {
+    pub styles: OutlineSelectionStyles,
+}
+
+impl Plugin for OutlineSelectionPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(self.styles).add_system(
+            update_selection_outlines
+                .in_base_set(CoreSet::PreUpdate)
+                .after(PickingSystem::Events),
+        );
+    }
+}
+
+/// The outline of each interaction state, the entities in a state without an outline aren't outlined.
+///
+/// When several states apply, the pressed outline is used first, then the hovered one, then the selected one.
+/// Use different [`crate::OutlineLayer`]s to give each state its own [`crate::OutlineSettings`].
+#[derive(Resource, Clone, Copy, Debug, PartialEq)]
+pub struct OutlineSelectionStyles {
+    pub hovered: Option<Outline>,
+    pub pressed: Option<Outline>,
+    pub selected: Option<Outline>,
+}
+
+impl Default for OutlineSelectionStyles {
+    fn default() -> Self {
+        Self {
+            hovered: Some(Outline {
+                color: Color::WHITE,
+                ..default()
+            }),
+            pressed: Some(Outline {
+                color: Color::GREEN,
+                ..default()
+            }),
+            selected: Some(Outline {
+                color: Color::ORANGE,
+                ..default()
+            }),
+        }
+    }
+}
+
+impl OutlineSelectionStyles {
+    /// The outline of an entity in the given states
+    fn outline(&self, pressed: bool, hovered: bool, selected: bool) -> Option<Outline> {
+        [
+            (pressed, self.pressed),
+            (hovered, self.hovered),
+            (selected, self.selected),
+        ]
+        .into_iter()
+        .find_map(|(state, outline)| outline.filter(|_| state))
+    }
+}
+
+/// What the entity had before the selection outlined it, restored once it isn't outlined anymore
+#[derive(Component)]
+pub struct SavedOutline {
+    outline: Option<Outline>,
+    tree: bool,
+    /// The style given to the entity, any other outline was set while it was outlined and replaces the saved one
+    style: Outline,
+}
+
+/// The [`OutlineTree`] inserted by the selection, kept until the outline has faded out with its [`OutlineTransition`]
+#[derive(Component)]
+pub struct FadingOutlineTree;
+
+pub fn update_selection_outlines(
+    mut commands: Commands,
+    styles: Res<OutlineSelectionStyles>,
+    pickables: Query<
+        (
+            Entity,
+            Option<&Interaction>,
+            Option<&Hover>,
+            Option<&Selection>,
+            Option<&Outline>,
+            Option<&OutlineTree>,
+            Option<&SavedOutline>,
+            Option<&OutlineTransition>,
+            Option<&OutlineTransitionState>,
+            Option<&FadingOutlineTree>,
+        ),
+        Or<(
+            With<Hover>,
+            With<Selection>,
+            With<SavedOutline>,
+            With<FadingOutlineTree>,
+        )>,
+    >,
+) {
+    for (entity, interaction, hover, selection, outline, tree, saved, transition, state, fading) in
+        &pickables
+    {
+        let style = styles.outline(
+            interaction == Some(&Interaction::Clicked),
+            hover.is_some_and(Hover::hovered),
+            selection.is_some_and(Selection::selected),
+        );
+        let mut entity_commands = commands.entity(entity);
+        match (style, saved) {
+            (Some(style), saved) => {
+                let saved_outline = match saved {
+                    Some(saved) if outline == Some(&saved.style) => saved.outline,
+                    _ => outline.copied(),
+                };
+                let up_to_date = saved
+                    .is_some_and(|saved| saved.outline == saved_outline && saved.style == style);
+                if !up_to_date {
+                    entity_commands.insert(SavedOutline {
+                        outline: saved_outline,
+                        tree: saved.map_or(tree.is_some() && fading.is_none(), |saved| saved.tree),
+                        style,
+                    });
+                }
+                if outline != Some(&style) {
+                    entity_commands.insert(style);
+                }
+                if tree.is_none() {
+                    entity_commands.insert(OutlineTree);
+                }
+                if fading.is_some() {
+                    entity_commands.remove::<FadingOutlineTree>();
+                }
+            }
+            (None, Some(saved)) => {
+                match saved.outline {
+                    Some(outline) => entity_commands.insert(outline),
+                    None => entity_commands.remove::<Outline>(),
+                };
+                // The descendants fade out with the entity
+                if !saved.tree && saved.outline.is_none() && transition.is_some() {
+                    entity_commands.insert(FadingOutlineTree);
+                } else if !saved.tree {
+                    entity_commands.remove::<OutlineTree>();
+                }
+                entity_commands.remove::<SavedOutline>();
+            }
+            (None, None) => {
+                if fading.is_some() && (outline.is_some() || state.is_none()) {
+                    entity_commands.remove::<(OutlineTree, FadingOutlineTree)>();
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn outline(color: Color) -> Outline {
+        Outline { color, ..default() }
+    }
+
+    fn update(world: &mut World) {
+        let mut schedule = Schedule::new();
+        schedule.add_system(update_selection_outlines);
+        schedule.run(world);
+    }
+
+    #[test]
+    fn pressed_then_hovered_then_selected() {
+        let styles = OutlineSelectionStyles::default();
+        assert_eq!(styles.outline(true, true, true), styles.pressed);
+        assert_eq!(styles.outline(false, true, true), styles.hovered);
+        assert_eq!(styles.outline(false, false, true), styles.selected);
+        assert_eq!(styles.outline(false, false, false), None);
+
+        let styles = OutlineSelectionStyles {
+            hovered: None,
+            ..styles
+        };
+        assert_eq!(styles.outline(false, true, true), styles.selected);
+    }
+
+    #[test]
+    fn restores_the_outline() {
+        let mut world = World::new();
+        world.insert_resource(OutlineSelectionStyles::default());
+        let styles = OutlineSelectionStyles::default();
+        let plain = world.spawn((Interaction::None, Selection::default())).id();
+        let outlined = world
+            .spawn((
+                Interaction::None,
+                Selection::default(),
+                outline(Color::BLUE),
+                OutlineTree,
+            ))
+            .id();
+
+        update(&mut world);
+        assert_eq!(world.get::<Outline>(plain), None);
+
+        for entity in [plain, outlined] {
+            world
+                .get_mut::<Selection>(entity)
+                .unwrap()
+                .set_selected(true);
+        }
+        update(&mut world);
+        for entity in [plain, outlined] {
+            assert_eq!(world.get::<Outline>(entity).copied(), styles.selected);
+            assert!(world.get::<OutlineTree>(entity).is_some());
+        }
+
+        for entity in [plain, outlined] {
+            world.entity_mut(entity).insert(Interaction::Clicked);
+        }
+        update(&mut world);
+        assert_eq!(world.get::<Outline>(plain).copied(), styles.pressed);
+
+        for entity in [plain, outlined] {
+            world.entity_mut(entity).insert(Interaction::None);
+            world
+                .get_mut::<Selection>(entity)
+                .unwrap()
+                .set_selected(false);
+        }
+        update(&mut world);
+        assert_eq!(world.get::<Outline>(plain), None);
+        assert!(world.get::<OutlineTree>(plain).is_none());
+        assert_eq!(world.get::<Outline>(outlined), Some(&outline(Color::BLUE)));
+        assert!(world.get::<OutlineTree>(outlined).is_some());
+    }
+
+    #[test]
+    fn keeps_the_outline_changed_while_selected() {
+        let mut world = World::new();
+        world.insert_resource(OutlineSelectionStyles::default());
+        let styles = OutlineSelectionStyles::default();
+        let entity = world
+            .spawn((
+                Interaction::None,
+                Selection::default(),
+                outline(Color::BLUE),
+            ))
+            .id();
+        world
+            .get_mut::<Selection>(entity)
+            .unwrap()
+            .set_selected(true);
+        update(&mut world);
+        assert_eq!(world.get::<Outline>(entity).copied(), styles.selected);
+
+        // The new outline is hidden by the style until the entity isn't selected anymore
+        world.entity_mut(entity).insert(outline(Color::RED));
+        update(&mut world);
+        assert_eq!(world.get::<Outline>(entity).copied(), styles.selected);
+
+        // Switching between styles doesn't replace it
+        world.entity_mut(entity).insert(Interaction::Clicked);
+        update(&mut world);
+        assert_eq!(world.get::<Outline>(entity).copied(), styles.pressed);
+
+        world.entity_mut(entity).insert(Interaction::None);
+        world
+            .get_mut::<Selection>(entity)
+            .unwrap()
+            .set_selected(false);
+        update(&mut world);
+        assert_eq!(world.get::<Outline>(entity), Some(&outline(Color::RED)));
+    }
+
+    #[test]
+    fn keeps_the_tree_while_fading_out() {
+        let mut world = World::new();
+        world.insert_resource(OutlineSelectionStyles::default());
+        let entity = world
+            .spawn((
+                Interaction::None,
+                Selection::default(),
+                OutlineTransition::default(),
+            ))
+            .id();
+        let select = |world: &mut World, selected: bool| {
+            world
+                .get_mut::<Selection>(entity)
+                .unwrap()
+                .set_selected(selected);
+            update(world);
+        };
+
+        select(&mut world, true);
+        select(&mut world, false);
+        assert_eq!(world.get::<Outline>(entity), None);
+        assert!(world.get::<OutlineTree>(entity).is_some());
+
+        // Selected again while fading out, the tree is still removed afterwards
+        select(&mut world, true);
+        assert!(world.get::<FadingOutlineTree>(entity).is_none());
+        select(&mut world, false);
+        assert!(world.get::<OutlineTree>(entity).is_some());
+
+        // The transition has ended without a state
+        update(&mut world);
+        assert!(world.get::<OutlineTree>(entity).is_none());
+        assert!(world.get::<FadingOutlineTree>(entity).is_none());
+    }
+}
@@ -8,13 +8,27 @@
 
 #import bevy_pbr::mesh_view_types
 #import bevy_pbr::mesh_types
+#import bevy_outline::stencil_types
 
 @group(0) @binding(0)
 var<uniform> view: View;
+@group(0) @binding(9)
+var<uniform> globals: Globals;
+#ifdef MULTISAMPLED
+@group(0) @binding(16)
+var depth_prepass_texture: texture_depth_multisampled_2d;
+#else
+@group(0) @binding(16)
+var depth_prepass_texture: texture_depth_2d;
+#endif
 
-struct StencilUniform {
-    color: vec4<f32>,
-};
+const OCCLUSION_ALWAYS_VISIBLE: u32 = 0u;
+const OCCLUSION_VISIBLE_ONLY: u32 = 1u;
+const OCCLUSION_XRAY: u32 = 2u;
+
+// Relative depth difference required to consider a fragment occluded,
+// this avoids the mesh occluding itself because of precision issues
+const OCCLUSION_BIAS: f32 = 0.001;
 
 @group(1) @binding(0)
 var<uniform> mesh: Mesh;
@@ -29,9 +43,24 @@ var<uniform> joint_matrices: SkinnedMesh;
 
 @group(2) @binding(0)
 var<uniform> stencil_uniform: StencilUniform;
+@group(2) @binding(1)
+var<uniform> stencil_layer: StencilLayer;
+
+#ifdef ALPHA_MASK
+@group(3) @binding(0)
+var base_color_texture: texture_2d<f32>;
+@group(3) @binding(1)
+var base_color_sampler: sampler;
+#endif
 
 struct Vertex {
     @location(0) position: vec3<f32>,
+#ifdef HULL
+    @location(1) normal: vec3<f32>,
+#endif
+#ifdef VERTEX_UVS
+    @location(2) uv: vec2<f32>,
+#endif
 #ifdef SKINNED
     @location(5) joint_indices: vec4<u32>,
     @location(6) joint_weights: vec4<f32>,
@@ -40,8 +69,33 @@ struct Vertex {
 
 struct VertexOutput {
     @builtin(position) clip_position: vec4<f32>,
+#ifdef VERTEX_UVS
+    @location(0) uv: vec2<f32>,
+#endif
+    // The view depth used by the world units and the distance to the camera used by the fade
+    @location(1) depth: vec2<f32>,
 };
 
+#ifdef HULL
+// Extrudes the vertex along its normal, either in world space or by a number of pixels on the screen
+fn hull_position(world_position: vec4<f32>, world_normal: vec3<f32>, distance: f32) -> vec4<f32> {
+    let width = min(stencil_uniform.width, stencil_layer.size) * stencil_uniform.visibility * width_fade(stencil_layer, distance);
+    if stencil_layer.world_units != 0u {
+        return view.view_proj * (world_position + vec4(world_normal * width, 0.0));
+    }
+
+    let clip_position = view.view_proj * world_position;
+    let clip_normal = (view.view_proj * vec4(world_normal, 0.0)).xy;
+    // Normals facing the camera don't move on the screen
+    if dot(clip_normal, clip_normal) == 0.0 {
+        return clip_position;
+    }
+    // The clip space spans 2 units across the viewport, the offset is scaled by w to survive the perspective divide
+    let offset = normalize(clip_normal) * width * stencil_layer.pixels_per_unit * 2.0 / view.viewport.zw;
+    return clip_position + vec4(offset * clip_position.w, 0.0, 0.0);
+}
+#endif
+
 @vertex
 fn vertex(vertex: Vertex) -> VertexOutput {
 #ifdef SKINNED
@@ -50,11 +104,75 @@ fn vertex(vertex: Vertex) -> VertexOutput {
     let model = mesh.model;
 #endif
     var out: VertexOutput;
-    out.clip_position = view.view_proj * model * vec4<f32>(vertex.position, 1.0);
+    let world_position = model * vec4<f32>(vertex.position, 1.0);
+    let distance = distance(world_position.xyz, view.world_position);
+#ifdef HULL
+#ifdef SKINNED
+    let world_normal = skin_normals(model, vertex.normal);
+#else
+    let world_normal = mesh_normal_local_to_world(vertex.normal);
+#endif
+    out.clip_position = hull_position(world_position, world_normal, distance);
+#else
+    out.clip_position = view.view_proj * world_position;
+#endif
+    out.depth = vec2(out.clip_position.w, distance);
+#ifdef VERTEX_UVS
+    out.uv = vertex.uv;
+#endif
+    return out;
+}
+
+struct FragmentOutput {
+    @location(0) color: vec4<f32>,
+    @location(1) data: vec4<f32>,
+    @location(2) rank: vec4<f32>,
+    @builtin(frag_depth) depth: f32,
+};
+
+fn is_occluded(frag_coord: vec4<f32>) -> bool {
+    // The stencil texture only covers the viewport but the prepass covers the whole target
+    let coord = vec2<i32>(frag_coord.xy + view.viewport.xy);
+    let scene_depth = textureLoad(depth_prepass_texture, coord, 0);
+    // Reversed-z, a smaller depth is further away
+    return frag_coord.z * (1.0 + OCCLUSION_BIAS) < scene_depth;
+}
+
+@fragment
+fn fragment(in: VertexOutput) -> FragmentOutput {
+    let frag_coord = in.clip_position;
+
+#ifdef ALPHA_MASK
+    var alpha = stencil_uniform.base_alpha;
+#ifdef VERTEX_UVS
+    alpha *= textureSample(base_color_texture, base_color_sampler, in.uv).a;
+#endif
+    if alpha < stencil_uniform.alpha_cutoff {
+        discard;
+    }
+#endif
+
+    var color = stencil_uniform.color;
+    if stencil_uniform.occlusion != OCCLUSION_ALWAYS_VISIBLE && is_occluded(frag_coord) {
+        if stencil_uniform.occlusion == OCCLUSION_VISIBLE_ONLY {
+            discard;
+        }
+        color = stencil_uniform.xray_color;
+    }
+
+    let width = pixel_width(stencil_uniform, stencil_layer, in.depth.x) * width_fade(stencil_layer, in.depth.y);
+    var out: FragmentOutput;
+    out.color = animate_color(stencil_uniform, color, globals.time) * alpha_fade(stencil_layer, in.depth.y);
+    out.data = stencil_data(stencil_uniform, width);
+    out.rank = overlap_rank(stencil_uniform, frag_coord.z);
+    out.depth = overlap_depth(out.rank);
     return out;
 }
 
+#ifdef HULL
+// The hull uses the animated color of the outline, it's blended with the view like the outlines of the other types
 @fragment
-fn fragment() -> @location(0) vec4<f32> {
-    return stencil_uniform.color;
+fn hull_fragment(in: VertexOutput) -> @location(0) vec4<f32> {
+    return animate_color(stencil_uniform, stencil_uniform.color, globals.time) * alpha_fade(stencil_layer, in.depth.y);
 }
+#endif
@@ -0,0 +1,102 @@
+#import bevy_sprite::mesh2d_view_bindings
+#import bevy_outline::stencil_types
+
+@group(1) @binding(0)
+var<uniform> stencil_uniform: StencilUniform;
+@group(1) @binding(1)
+var<uniform> stencil_layer: StencilLayer;
+
+// Sprites with a smaller alpha are considered transparent and don't write to the stencil
+const ALPHA_THRESHOLD: f32 = 0.5;
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+#ifdef SPRITE
+    @location(0) uv: vec2<f32>,
+#endif
+    // The view depth used by the world units and the distance to the camera used by the fade
+    @location(1) depth: vec2<f32>,
+};
+
+// The 2d camera is far in front of the sprites, the fade only uses the distance in their plane
+fn vertex_depth(clip_position: vec4<f32>, world_position: vec4<f32>) -> vec2<f32> {
+    return vec2(clip_position.w, distance(world_position.xy, view.world_position.xy));
+}
+
+#ifdef SPRITE
+struct SpriteStencil {
+    transform: mat4x4<f32>,
+    uv_rect: vec4<f32>,
+    alpha: f32,
+};
+
+@group(2) @binding(0)
+var sprite_texture: texture_2d<f32>;
+@group(2) @binding(1)
+var sprite_sampler: sampler;
+@group(2) @binding(2)
+var<uniform> sprite: SpriteStencil;
+
+// The sprite quad is generated from the vertex index, there's no vertex buffer
+@vertex
+fn sprite_vertex(@builtin(vertex_index) vertex_index: u32) -> VertexOutput {
+    var corners = array<vec2<f32>, 6>(
+        vec2(0.0, 0.0),
+        vec2(1.0, 0.0),
+        vec2(1.0, 1.0),
+        vec2(0.0, 0.0),
+        vec2(1.0, 1.0),
+        vec2(0.0, 1.0),
+    );
+    let corner = corners[vertex_index];
+
+    let world_position = sprite.transform * vec4(corner, 0.0, 1.0);
+    var out: VertexOutput;
+    out.clip_position = view.view_proj * world_position;
+    out.uv = mix(sprite.uv_rect.xy, sprite.uv_rect.zw, corner);
+    out.depth = vertex_depth(out.clip_position, world_position);
+    return out;
+}
+#else
+#import bevy_sprite::mesh2d_bindings
+#import bevy_sprite::mesh2d_functions
+
+struct Vertex {
+    @location(0) position: vec3<f32>,
+};
+
+@vertex
+fn vertex(vertex: Vertex) -> VertexOutput {
+    let world_position = mesh2d_position_local_to_world(mesh.model, vec4(vertex.position, 1.0));
+    var out: VertexOutput;
+    out.clip_position = mesh2d_position_world_to_clip(world_position);
+    out.depth = vertex_depth(out.clip_position, world_position);
+    return out;
+}
+#endif
+
+struct FragmentOutput {
+    @location(0) color: vec4<f32>,
+    @location(1) data: vec4<f32>,
+    @location(2) rank: vec4<f32>,
+    @builtin(frag_depth) depth: f32,
+};
+
+// There's no depth prepass in 2d so the occlusion is ignored
+@fragment
+fn fragment(in: VertexOutput) -> FragmentOutput {
+#ifdef SPRITE
+    let alpha = textureSample(sprite_texture, sprite_sampler, in.uv).a * sprite.alpha;
+    if alpha < ALPHA_THRESHOLD {
+        discard;
+    }
+#endif
+
+    let width = pixel_width(stencil_uniform, stencil_layer, in.depth.x) * width_fade(stencil_layer, in.depth.y);
+    var out: FragmentOutput;
+    out.color = animate_color(stencil_uniform, stencil_uniform.color, globals.time) * alpha_fade(stencil_layer, in.depth.y);
+    out.data = stencil_data(stencil_uniform, width);
+    out.rank = overlap_rank(stencil_uniform, in.clip_position.z);
+    out.depth = overlap_depth(out.rank);
+    return out;
+}
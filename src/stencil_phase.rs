@@ -3,12 +3,12 @@ use bevy::{
     ecs::{
         query::ROQueryItem,
         system::{
-            lifetimeless::{Read, SRes},
+            lifetimeless::{Read, SQuery, SRes},
             SystemParamItem,
         },
     },
     pbr::{
-        DrawMesh, MeshPipeline, MeshPipelineKey, MeshUniform, SetMeshBindGroup,
+        AlphaMode, DrawMesh, MeshPipeline, MeshPipelineKey, MeshUniform, SetMeshBindGroup,
         SetMeshViewBindGroup,
     },
     prelude::*,
@@ -24,36 +24,73 @@ use bevy::{
         },
         render_resource::{
             BindGroup, BindGroupDescriptor, BindGroupLayout, BindGroupLayoutDescriptor,
-            BindingType, BufferBindingType, CachedRenderPipelineId, PipelineCache,
-            RenderPipelineDescriptor, ShaderType, SpecializedMeshPipeline,
-            SpecializedMeshPipelineError, SpecializedMeshPipelines,
+            BindingResource, BindingType, BlendState, BufferBindingType, CachedRenderPipelineId,
+            ColorTargetState, ColorWrites, CompareFunction, DepthStencilState, Face, PipelineCache,
+            RenderPipelineDescriptor, SamplerBindingType, ShaderType, SpecializedMeshPipeline,
+            SpecializedMeshPipelineError, SpecializedMeshPipelines, TextureFormat,
+            TextureSampleType, TextureViewDimension,
         },
         renderer::RenderDevice,
         view::{ExtractedView, VisibleEntities},
         Extract, RenderApp, RenderSet,
     },
-    utils::{FixedState, FloatOrd, Hashed},
+    utils::{FixedState, FloatOrd, HashMap, HashSet, Hashed},
 };
 
 use crate::{
     bind_group_entries, bind_group_layout_entries,
+    transition::{displayed_outline, DisplayedOutline, OutlineTransitionState},
     utils::{color_target, fragment_state},
-    Outline,
+    viewport::OutlineViewport,
+    width::{is_perspective, pixels_per_unit},
+    Outline, OutlineAnimation, OutlineFade, OutlineGroup, OutlineLayer, OutlineOcclusion,
+    OutlineOverlap, OutlineSettings, OutlineType, OutlineWidthUnit, ViewOutlineLayer,
+    ViewOutlineLayers,
 };
 
 pub const STENCIL_SHADER_HANDLE: HandleUntyped =
     HandleUntyped::weak_from_u64(Shader::TYPE_UUID, 15139276207022888006);
 
+/// Types and functions shared by the 3d and 2d stencil shaders
+pub const STENCIL_TYPES_SHADER_HANDLE: HandleUntyped =
+    HandleUntyped::weak_from_u64(Shader::TYPE_UUID, 2870526491835112094);
+
+/// Format of the second target of the stencil pass, the red channel stores the width of the outline,
+/// the green and blue channels store the length and speed of the dashes
+pub const STENCIL_DATA_TEXTURE_FORMAT: TextureFormat = TextureFormat::Rgba16Float;
+
+/// Format of the third target of the stencil pass, it stores the rank used by [`OutlineOverlap`].
+/// The red channel is the tier of the outline and the green channel is its depth, a higher rank wins.
+/// The blue channel is 1 for outlines with [`Outline::interior_lines`], the alpha channel is the [`OutlineGroup`].
+pub const STENCIL_RANK_TEXTURE_FORMAT: TextureFormat = TextureFormat::Rgba16Float;
+
+/// Only the highest rank is kept inside the stencil, the depth is derived from the rank
+pub const STENCIL_DEPTH_FORMAT: TextureFormat = TextureFormat::Depth32Float;
+
+/// Width used when an [`Outline`] doesn't specify one, this is the biggest value that fits in the
+/// stencil data texture. It always ends up being clamped to the size of the outline of the view.
+const MAX_WIDTH: f32 = 65504.0;
+
+/// Alpha cutoff used by [`Outline::alpha_mask`] when the material isn't using [`AlphaMode::Mask`]
+const DEFAULT_ALPHA_CUTOFF: f32 = 0.5;
+
 pub struct MeshStencilPlugin;
 impl Plugin for MeshStencilPlugin {
     fn build(&self, app: &mut App) {
+        load_internal_asset!(
+            app,
+            STENCIL_TYPES_SHADER_HANDLE,
+            "stencil_types.wgsl",
+            Shader::from_wgsl
+        );
         load_internal_asset!(
             app,
             STENCIL_SHADER_HANDLE,
             "stencil.wgsl",
             Shader::from_wgsl
         );
-        app.add_plugin(UniformComponentPlugin::<StencilUniform>::default());
+        app.add_plugin(UniformComponentPlugin::<StencilUniform>::default())
+            .add_plugin(UniformComponentPlugin::<StencilLayerUniform>::default());
 
         let Ok(render_app) = app.get_sub_app_mut(RenderApp) else {
             return;
@@ -62,22 +99,52 @@ impl Plugin for MeshStencilPlugin {
         render_app
             .init_resource::<StencilPipeline>()
             .init_resource::<SpecializedMeshPipelines<StencilPipeline>>()
+            .init_resource::<AlphaMaskBindGroups>()
+            .init_resource::<InteriorLinesLayers>()
+            .init_resource::<GroupEdgesLayers>()
             .init_resource::<DrawFunctions<MeshStencil>>()
             .add_render_command::<MeshStencil, DrawMeshStencil>()
+            .add_render_command::<MeshStencil, DrawMeshStencilAlphaMask>()
+            .add_render_command::<MeshStencil, DrawMeshHull>()
             .add_system(sort_phase_system::<MeshStencil>.in_set(RenderSet::PhaseSort))
             .add_systems(
                 (extract_stencil_phase, extract_stencil_uniform).in_schedule(ExtractSchedule),
             )
             .add_system(queue_stencil_bind_group.in_set(RenderSet::Queue))
+            .add_system(queue_alpha_mask_bind_groups.in_set(RenderSet::Queue))
             .add_system(queue_mesh_stencil.in_set(RenderSet::Queue));
     }
 }
 
+/// A [`PhaseItem`] drawn to the stencil of a single [`OutlineLayer`]
+pub trait StencilPhaseItem: PhaseItem {
+    fn layer(&self) -> OutlineLayer;
+}
+
+/// Renders the items of the given layer, the other items are skipped
+pub fn render_stencil_layer<'w, I: StencilPhaseItem>(
+    phase: &RenderPhase<I>,
+    layer: OutlineLayer,
+    pass: &mut TrackedRenderPass<'w>,
+    world: &'w World,
+    view: Entity,
+) {
+    let draw_functions = world.resource::<DrawFunctions<I>>();
+    let mut draw_functions = draw_functions.write();
+    draw_functions.prepare(world);
+
+    for item in phase.items.iter().filter(|item| item.layer() == layer) {
+        let draw_function = draw_functions.get_mut(item.draw_function()).unwrap();
+        draw_function.draw(world, pass, view, item);
+    }
+}
+
 pub struct MeshStencil {
     pub distance: f32,
     pub pipeline: CachedRenderPipelineId,
     pub entity: Entity,
     pub draw_function: DrawFunctionId,
+    pub layer: OutlineLayer,
 }
 
 impl PhaseItem for MeshStencil {
@@ -96,37 +163,246 @@ impl PhaseItem for MeshStencil {
     }
 }
 
+impl StencilPhaseItem for MeshStencil {
+    fn layer(&self) -> OutlineLayer {
+        self.layer
+    }
+}
+
 impl CachedRenderPipelinePhaseItem for MeshStencil {
     fn cached_pipeline(&self) -> CachedRenderPipelineId {
         self.pipeline
     }
 }
 
+// Must match the constants in stencil.wgsl
+const OCCLUSION_ALWAYS_VISIBLE: u32 = 0;
+const OCCLUSION_VISIBLE_ONLY: u32 = 1;
+const OCCLUSION_XRAY: u32 = 2;
+
+/// Tiers of [`OutlineOverlap`], they must stay below 2048 to be stored exactly in the rank texture
+const OVERLAP_TIER_BLEND: f32 = 0.0;
+const OVERLAP_TIER_NEAREST: f32 = 1.0;
+const MAX_OVERLAP_PRIORITY: i32 = 1000;
+
+fn overlap_tier(overlap: OutlineOverlap) -> f32 {
+    match overlap {
+        OutlineOverlap::Blend => OVERLAP_TIER_BLEND,
+        OutlineOverlap::Nearest => OVERLAP_TIER_NEAREST,
+        OutlineOverlap::Priority(priority) => {
+            let priority = priority.clamp(-MAX_OVERLAP_PRIORITY, MAX_OVERLAP_PRIORITY);
+            OVERLAP_TIER_NEAREST + (priority + MAX_OVERLAP_PRIORITY + 1) as f32
+        }
+    }
+}
+
+/// Number of [`OutlineGroup`]s of a layer that can be told apart, integers up to 2048 are stored exactly in the rank texture
+const GROUP_COUNT: usize = 2048;
+
+/// The index of every [`OutlineGroup`] of every layer, the rank texture stores it instead of the id.
+///
+/// The groups of a layer are numbered in the order of their ids, any ids can be used as long as a layer
+/// doesn't have more than [`GROUP_COUNT`] groups. The extra groups share the last index.
+/// Returns the indices and whether some groups had to share an index.
+fn group_indices(
+    groups: impl Iterator<Item = (OutlineLayer, u32)>,
+) -> (HashMap<(OutlineLayer, u32), f32>, bool) {
+    let mut groups: Vec<_> = groups.collect();
+    groups.sort_unstable();
+    groups.dedup();
+    let mut indices = HashMap::default();
+    let mut merged = false;
+    let mut index = 0;
+    for (i, (layer, group)) in groups.iter().copied().enumerate() {
+        index = match i > 0 && groups[i - 1].0 == layer {
+            true => index + 1,
+            false => 0,
+        };
+        merged |= index >= GROUP_COUNT;
+        indices.insert((layer, group), index.min(GROUP_COUNT - 1) as f32);
+    }
+    (indices, merged)
+}
+
+/// The targets of the stencil pass, shared by the 3d and 2d pipelines
+pub fn stencil_targets(hdr: bool) -> [ColorTargetState; 3] {
+    [
+        color_target(None, hdr),
+        ColorTargetState {
+            format: STENCIL_DATA_TEXTURE_FORMAT,
+            blend: None,
+            write_mask: ColorWrites::ALL,
+        },
+        ColorTargetState {
+            format: STENCIL_RANK_TEXTURE_FORMAT,
+            blend: None,
+            write_mask: ColorWrites::ALL,
+        },
+    ]
+}
+
+/// Keeps the outline with the highest rank inside the stencil, the shaders write the rank as the depth
+pub fn stencil_depth_state() -> DepthStencilState {
+    DepthStencilState {
+        format: STENCIL_DEPTH_FORMAT,
+        depth_write_enabled: true,
+        depth_compare: CompareFunction::GreaterEqual,
+        stencil: default(),
+        bias: default(),
+    }
+}
+
 #[derive(Component, ShaderType, Clone, Copy)]
 pub struct StencilUniform {
     color: Color,
+    width: f32,
+    occlusion: u32,
+    xray_color: Color,
+    /// Only used with [`Outline::alpha_mask`]
+    alpha_cutoff: f32,
+    base_alpha: f32,
+    pulse_amplitude: f32,
+    pulse_frequency: f32,
+    hue_speed: f32,
+    dash_length: f32,
+    dash_speed: f32,
+    /// The tier of the [`OutlineOverlap`]
+    overlap_tier: f32,
+    interior_lines: u32,
+    /// The index of the [`OutlineGroup`] in its layer
+    group: f32,
+    /// Scales the width and the color while an [`crate::OutlineTransition`] fades the outline in or out
+    visibility: f32,
 }
 
-pub struct SetStencilBindGroup<const I: usize>;
-impl<P: PhaseItem, const I: usize> RenderCommand<P> for SetStencilBindGroup<I> {
-    type Param = SRes<StencilBindGroup>;
-    type ViewWorldQuery = ();
+/// Settings of a layer used by the stencil, they convert the widths of the outlines to pixels and fade them
+#[derive(Component, ShaderType, Clone, Copy)]
+pub struct StencilLayerUniform {
+    /// Maximum width of the outlines, in the [`OutlineWidthUnit`] of the layer
+    size: f32,
+    pixels_per_unit: f32,
+    world_units: u32,
+    /// World units are divided by the depth of the fragment
+    perspective: u32,
+    fade_start: f32,
+    fade_end: f32,
+    fade_width: u32,
+    fade_alpha: u32,
+}
+
+impl StencilLayerUniform {
+    pub fn new(settings: &OutlineSettings, projection: Mat4, viewport: &OutlineViewport) -> Self {
+        let fade = settings.fade.unwrap_or(OutlineFade {
+            width: false,
+            alpha: false,
+            ..default()
+        });
+        Self {
+            size: settings.size.max(0.0),
+            pixels_per_unit: pixels_per_unit(settings.width_unit, projection, viewport),
+            world_units: (settings.width_unit == OutlineWidthUnit::World).into(),
+            perspective: is_perspective(projection).into(),
+            fade_start: fade.start,
+            fade_end: fade.end.max(fade.start),
+            fade_width: fade.width.into(),
+            fade_alpha: fade.alpha.into(),
+        }
+    }
+}
+
+/// The layers with at least one [`Outline::interior_lines`], only they run the interior lines pass
+#[derive(Resource, Default)]
+pub struct InteriorLinesLayers(pub HashSet<OutlineLayer>);
+
+/// The layers with outlines of at least two [`OutlineGroup`]s, only they run the group edges pass
+#[derive(Resource, Default)]
+pub struct GroupEdgesLayers(pub HashSet<OutlineLayer>);
+
+/// The texture of the material used to discard transparent texels of meshes using [`Outline::alpha_mask`]
+#[derive(Component)]
+pub struct StencilAlphaMask {
+    /// The alpha of the material is used for the whole mesh when it doesn't have a texture
+    texture: Option<Handle<Image>>,
+}
+
+pub(crate) struct SetStencilBindGroup<const I: usize>;
+impl<P: StencilPhaseItem, const I: usize> RenderCommand<P> for SetStencilBindGroup<I> {
+    type Param = (
+        SRes<StencilBindGroup>,
+        SQuery<(
+            Read<ViewOutlineLayer>,
+            Read<DynamicUniformIndex<StencilLayerUniform>>,
+        )>,
+    );
+    type ViewWorldQuery = Read<ViewOutlineLayers>;
     type ItemWorldQuery = Read<DynamicUniformIndex<StencilUniform>>;
 
+    #[inline]
+    fn render<'w>(
+        item: &P,
+        view_layers: ROQueryItem<'w, Self::ViewWorldQuery>,
+        mesh_index: ROQueryItem<'w, Self::ItemWorldQuery>,
+        (bind_group, layers): SystemParamItem<'w, '_, Self::Param>,
+        pass: &mut TrackedRenderPass<'w>,
+    ) -> RenderCommandResult {
+        // The settings of the layer are stored on the render entity of the layer of the item
+        let layer_index = view_layers.0.iter().find_map(|entity| {
+            let (layer, layer_index) = layers.get_inner(*entity).ok()?;
+            (layer.layer == item.layer()).then_some(layer_index)
+        });
+        let Some(layer_index) = layer_index else {
+            return RenderCommandResult::Failure;
+        };
+        pass.set_bind_group(
+            I,
+            &bind_group.into_inner().value,
+            &[mesh_index.index(), layer_index.index()],
+        );
+        RenderCommandResult::Success
+    }
+}
+
+pub struct SetAlphaMaskBindGroup<const I: usize>;
+impl<P: PhaseItem, const I: usize> RenderCommand<P> for SetAlphaMaskBindGroup<I> {
+    type Param = SRes<AlphaMaskBindGroups>;
+    type ViewWorldQuery = ();
+    type ItemWorldQuery = Read<StencilAlphaMask>;
+
     #[inline]
     fn render<'w>(
         _item: &P,
         _view: (),
-        mesh_index: ROQueryItem<'w, Self::ItemWorldQuery>,
-        resource: SystemParamItem<'w, '_, Self::Param>,
+        alpha_mask: ROQueryItem<'w, Self::ItemWorldQuery>,
+        bind_groups: SystemParamItem<'w, '_, Self::Param>,
         pass: &mut TrackedRenderPass<'w>,
     ) -> RenderCommandResult {
-        pass.set_bind_group(I, &resource.into_inner().value, &[mesh_index.index()]);
+        let Some(bind_group) = bind_groups.into_inner().values.get(&alpha_mask.texture) else {
+            return RenderCommandResult::Failure;
+        };
+        pass.set_bind_group(I, bind_group, &[]);
         RenderCommandResult::Success
     }
 }
 
-pub type DrawMeshStencil = (
+pub(crate) type DrawMeshStencil = (
+    SetItemPipeline,
+    SetMeshViewBindGroup<0>,
+    SetMeshBindGroup<1>,
+    SetStencilBindGroup<2>,
+    DrawMesh,
+);
+
+pub(crate) type DrawMeshStencilAlphaMask = (
+    SetItemPipeline,
+    SetMeshViewBindGroup<0>,
+    SetMeshBindGroup<1>,
+    SetStencilBindGroup<2>,
+    SetAlphaMaskBindGroup<3>,
+    DrawMesh,
+);
+
+/// Draws the inverted hull of a mesh directly to the view, see [`OutlineType::InvertedHull`]
+pub(crate) type DrawMeshHull = (
     SetItemPipeline,
     SetMeshViewBindGroup<0>,
     SetMeshBindGroup<1>,
@@ -134,10 +410,18 @@ pub type DrawMeshStencil = (
     DrawMesh,
 );
 
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct StencilPipelineKey {
+    mesh_key: MeshPipelineKey,
+    alpha_mask: bool,
+    hull: bool,
+}
+
 #[derive(Resource)]
 pub struct StencilPipeline {
     mesh_pipeline: MeshPipeline,
-    stencil_bind_group_layout: BindGroupLayout,
+    pub(crate) stencil_bind_group_layout: BindGroupLayout,
+    alpha_mask_bind_group_layout: BindGroupLayout,
 }
 
 impl FromWorld for StencilPipeline {
@@ -153,6 +437,26 @@ impl FromWorld for StencilPipeline {
                         has_dynamic_offset: true,
                         min_binding_size: Some(StencilUniform::min_size()),
                     },
+                    1 => BindingType::Buffer {
+                        ty: BufferBindingType::Uniform,
+                        has_dynamic_offset: true,
+                        min_binding_size: Some(StencilLayerUniform::min_size()),
+                    },
+                ],
+            });
+
+        let alpha_mask_bind_group_layout =
+            render_device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+                label: Some("stencil_alpha_mask_bind_group_layout"),
+                entries: &bind_group_layout_entries![
+                    // base color texture
+                    0 => BindingType::Texture {
+                        sample_type: TextureSampleType::Float { filterable: true },
+                        view_dimension: TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    // base color sampler
+                    1 => BindingType::Sampler(SamplerBindingType::Filtering),
                 ],
             });
 
@@ -160,23 +464,25 @@ impl FromWorld for StencilPipeline {
         StencilPipeline {
             mesh_pipeline,
             stencil_bind_group_layout,
+            alpha_mask_bind_group_layout,
         }
     }
 }
 
 impl SpecializedMeshPipeline for StencilPipeline {
-    type Key = MeshPipelineKey;
+    type Key = StencilPipelineKey;
 
     fn specialize(
         &self,
         key: Self::Key,
         layout: &Hashed<InnerMeshVertexBufferLayout, FixedState>,
     ) -> Result<RenderPipelineDescriptor, SpecializedMeshPipelineError> {
-        let mut desc = self.mesh_pipeline.specialize(key, layout)?;
+        // The mesh pipeline already adds the uv attribute and the VERTEX_UVS shader def when the mesh has uvs
+        let mut desc = self.mesh_pipeline.specialize(key.mesh_key, layout)?;
 
         desc.label = Some("mesh_stencil_pipeline".into());
 
-        let mut bind_group_layout = match key.msaa_samples() {
+        let mut bind_group_layout = match key.mesh_key.msaa_samples() {
             1 => vec![self.mesh_pipeline.view_layout.clone()],
             _ => {
                 vec![self.mesh_pipeline.view_layout_multisampled.clone()]
@@ -188,16 +494,42 @@ impl SpecializedMeshPipeline for StencilPipeline {
             bind_group_layout.push(self.mesh_pipeline.mesh_layout.clone());
         };
         bind_group_layout.push(self.stencil_bind_group_layout.clone());
+        if key.alpha_mask {
+            bind_group_layout.push(self.alpha_mask_bind_group_layout.clone());
+            desc.vertex.shader_defs.push("ALPHA_MASK".into());
+        }
+        if key.hull {
+            desc.vertex.shader_defs.push("HULL".into());
+        }
 
         desc.layout = bind_group_layout;
         desc.vertex.shader = STENCIL_SHADER_HANDLE.typed::<Shader>();
+        // The fragment shader needs to know if the depth prepass texture is multisampled
+        let shader_defs = desc.vertex.shader_defs.clone();
+        let hdr = key.mesh_key.contains(MeshPipelineKey::HDR);
+        if key.hull {
+            // The hull is drawn directly to the view and keeps the depth state of the mesh pipeline.
+            // Culling the front faces only leaves the parts of the hull sticking out of the mesh.
+            desc.label = Some("mesh_hull_pipeline".into());
+            desc.primitive.cull_mode = Some(Face::Front);
+            desc.fragment = fragment_state(
+                STENCIL_SHADER_HANDLE,
+                "hull_fragment",
+                &[color_target(
+                    Some(BlendState::PREMULTIPLIED_ALPHA_BLENDING),
+                    hdr,
+                )],
+                &shader_defs,
+            );
+            return Ok(desc);
+        }
         desc.fragment = fragment_state(
             STENCIL_SHADER_HANDLE,
             "fragment",
-            &[color_target(None)],
-            &[],
+            &stencil_targets(hdr),
+            &shader_defs,
         );
-        desc.depth_stencil = None;
+        desc.depth_stencil = Some(stencil_depth_state());
 
         Ok(desc)
     }
@@ -215,14 +547,93 @@ pub fn extract_stencil_phase(
     }
 }
 
-/// Create the StencilUniform for each mesh with an Outline component
+/// Create the StencilUniform for each mesh with an Outline component,
+/// and find the layers with interior lines or several groups
 pub fn extract_stencil_uniform(
     mut commands: Commands,
-    outlines: Extract<Query<(Entity, &Outline)>>,
+    mut interior_lines_layers: ResMut<InteriorLinesLayers>,
+    mut group_edges_layers: ResMut<GroupEdgesLayers>,
+    mut warned_merged_groups: Local<bool>,
+    materials: Extract<Res<Assets<StandardMaterial>>>,
+    outlines: Extract<
+        Query<
+            (
+                Entity,
+                Option<&Outline>,
+                Option<&OutlineTransitionState>,
+                Option<&OutlineAnimation>,
+                Option<&Handle<StandardMaterial>>,
+                Option<&OutlineGroup>,
+            ),
+            Or<(With<Outline>, With<OutlineTransitionState>)>,
+        >,
+    >,
 ) {
-    for (entity, outline) in &outlines {
-        commands.get_or_spawn(entity).insert(StencilUniform {
+    interior_lines_layers.0.clear();
+    group_edges_layers.0.clear();
+    let (group_indices, merged_groups) =
+        group_indices(outlines.iter().filter_map(|(_, outline, transition, .., group)| {
+            let displayed = displayed_outline(outline, transition)?;
+            Some((displayed.outline.layer, group.map_or(0, |group| group.0)))
+        }));
+    if merged_groups && !*warned_merged_groups {
+        warn!("An outline layer has more than {GROUP_COUNT} OutlineGroups, some of them are outlined together");
+        *warned_merged_groups = true;
+    }
+    for (entity, outline, transition, animation, material, group) in &outlines {
+        // Outlines fading out don't have an Outline anymore
+        let Some(DisplayedOutline { outline, visibility }) = displayed_outline(outline, transition) else {
+            continue;
+        };
+        if outline.interior_lines {
+            interior_lines_layers.0.insert(outline.layer);
+        }
+        let group = group_indices[&(outline.layer, group.map_or(0, |group| group.0))];
+        // Only the first group of a layer has the index 0
+        if group > 0.0 {
+            group_edges_layers.0.insert(outline.layer);
+        }
+        let (occlusion, xray_color) = match outline.occlusion {
+            OutlineOcclusion::AlwaysVisible => (OCCLUSION_ALWAYS_VISIBLE, Color::NONE),
+            OutlineOcclusion::VisibleOnly => (OCCLUSION_VISIBLE_ONLY, Color::NONE),
+            OutlineOcclusion::XRay { color } => (OCCLUSION_XRAY, color),
+        };
+        let animation = animation.copied().unwrap_or_default();
+        let mut entity_commands = commands.get_or_spawn(entity);
+        let material = material
+            .filter(|_| outline.alpha_mask)
+            .and_then(|material| materials.get(material));
+        let (alpha_cutoff, base_alpha) = match material {
+            Some(material) => {
+                entity_commands.insert(StencilAlphaMask {
+                    texture: material.base_color_texture.as_ref().map(Handle::clone_weak),
+                });
+                let alpha_cutoff = match material.alpha_mode {
+                    AlphaMode::Mask(cutoff) => cutoff,
+                    _ => DEFAULT_ALPHA_CUTOFF,
+                };
+                (alpha_cutoff, material.base_color.a())
+            }
+            None => (0.0, 1.0),
+        };
+        entity_commands.insert(StencilUniform {
             color: outline.color,
+            width: outline
+                .width
+                .map_or(MAX_WIDTH, |width| width.clamp(0.0, MAX_WIDTH)),
+            occlusion,
+            xray_color,
+            alpha_cutoff,
+            base_alpha,
+            pulse_amplitude: animation.pulse_amplitude.clamp(0.0, 1.0),
+            pulse_frequency: animation.pulse_frequency,
+            hue_speed: animation.hue_speed,
+            dash_length: animation.dash_length.max(0.0),
+            dash_speed: animation.dash_speed,
+            overlap_tier: overlap_tier(outline.overlap),
+            interior_lines: outline.interior_lines.into(),
+            group,
+            visibility,
         });
     }
 }
@@ -238,8 +649,9 @@ pub fn queue_stencil_bind_group(
     stencil_pipeline: Res<StencilPipeline>,
     render_device: Res<RenderDevice>,
     uniforms: Res<ComponentUniforms<StencilUniform>>,
+    layer_uniforms: Res<ComponentUniforms<StencilLayerUniform>>,
 ) {
-    let Some(uniform) = uniforms.binding() else {
+    let (Some(uniform), Some(layer_uniform)) = (uniforms.binding(), layer_uniforms.binding()) else {
         return;
     };
 
@@ -248,6 +660,7 @@ pub fn queue_stencil_bind_group(
         layout: &stencil_pipeline.stencil_bind_group_layout,
         entries: &bind_group_entries![
             0 => uniform.clone(),
+            1 => layer_uniform.clone(),
         ],
     });
 
@@ -256,41 +669,106 @@ pub fn queue_stencil_bind_group(
     });
 }
 
-/// Add any visible entity with a mesh and an [`Outline`] to the stencil_phase
-pub fn queue_mesh_stencil(
+/// Bind groups of the textures used by [`StencilAlphaMask`]
+#[derive(Resource, Default)]
+pub struct AlphaMaskBindGroups {
+    values: HashMap<Option<Handle<Image>>, BindGroup>,
+}
+
+/// Creates a bind group for every texture used by a [`StencilAlphaMask`].
+/// Materials without a texture use a white texture.
+pub fn queue_alpha_mask_bind_groups(
+    mut bind_groups: ResMut<AlphaMaskBindGroups>,
+    stencil_pipeline: Res<StencilPipeline>,
+    render_device: Res<RenderDevice>,
+    gpu_images: Res<RenderAssets<Image>>,
+    alpha_masks: Query<&StencilAlphaMask>,
+) {
+    bind_groups.values.clear();
+
+    for alpha_mask in &alpha_masks {
+        if bind_groups.values.contains_key(&alpha_mask.texture) {
+            continue;
+        }
+        let gpu_image = match &alpha_mask.texture {
+            Some(texture) => gpu_images.get(texture),
+            None => Some(&stencil_pipeline.mesh_pipeline.dummy_white_gpu_image),
+        };
+        let Some(gpu_image) = gpu_image else {
+            continue;
+        };
+        let bind_group = render_device.create_bind_group(&BindGroupDescriptor {
+            label: Some("stencil_alpha_mask_bind_group"),
+            layout: &stencil_pipeline.alpha_mask_bind_group_layout,
+            entries: &bind_group_entries![
+                0 => BindingResource::TextureView(&gpu_image.texture_view),
+                1 => BindingResource::Sampler(&gpu_image.sampler),
+            ],
+        });
+        bind_groups
+            .values
+            .insert(alpha_mask.texture.clone(), bind_group);
+    }
+}
+
+/// Add any visible entity with a mesh and an [`Outline`] to the stencil_phase.
+/// The entities of [`OutlineType::InvertedHull`] layers use the hull pipeline instead.
+pub(crate) fn queue_mesh_stencil(
     stencil_draw_functions: Res<DrawFunctions<MeshStencil>>,
     stencil_pipeline: Res<StencilPipeline>,
     mut pipelines: ResMut<SpecializedMeshPipelines<StencilPipeline>>,
     pipeline_cache: Res<PipelineCache>,
     render_meshes: Res<RenderAssets<Mesh>>,
-    outline_meshes: Query<(Entity, &Handle<Mesh>, &MeshUniform), With<Outline>>,
+    outline_meshes: Query<(
+        Entity,
+        &Outline,
+        &Handle<Mesh>,
+        &MeshUniform,
+        Option<&StencilAlphaMask>,
+    )>,
     mut views: Query<(
         &ExtractedView,
         &mut VisibleEntities,
         &mut RenderPhase<MeshStencil>,
+        Option<&ViewOutlineLayers>,
     )>,
+    layers: Query<(&ViewOutlineLayer, &OutlineSettings)>,
     msaa: Res<Msaa>,
 ) {
-    let draw_mesh_stencil = stencil_draw_functions
-        .read()
-        .get_id::<DrawMeshStencil>()
-        .unwrap();
+    let draw_functions = stencil_draw_functions.read();
+    let draw_mesh_stencil = draw_functions.get_id::<DrawMeshStencil>().unwrap();
+    let draw_mesh_stencil_alpha_mask = draw_functions.get_id::<DrawMeshStencilAlphaMask>().unwrap();
+    let draw_mesh_hull = draw_functions.get_id::<DrawMeshHull>().unwrap();
 
-    for (view, visible_entities, mut stencil_phase) in views.iter_mut() {
+    for (view, visible_entities, mut stencil_phase, view_layers) in views.iter_mut() {
         let view_matrix = view.transform.compute_matrix();
         let inv_view_row_2 = view_matrix.inverse().row(2);
 
-        let view_key = MeshPipelineKey::from_msaa_samples(msaa.samples());
+        // The colors of the stencil are stored in the format of the view
+        let view_key = MeshPipelineKey::from_msaa_samples(msaa.samples())
+            | MeshPipelineKey::from_hdr(view.hdr);
+        let hull_layers: Vec<_> = view_layers
+            .into_iter()
+            .flat_map(|view_layers| layers.iter_many(&view_layers.0))
+            .filter(|(_, settings)| settings.outline_type == OutlineType::InvertedHull)
+            .map(|(layer, _)| layer.layer)
+            .collect();
 
         for visible_entity in visible_entities.entities.iter().copied() {
-            let Ok((entity, mesh_handle, mesh_uniform)) = outline_meshes.get(visible_entity) else {
+            let Ok((entity, outline, mesh_handle, mesh_uniform, alpha_mask)) = outline_meshes.get(visible_entity) else {
                 continue;
             };
             let Some(mesh) = render_meshes.get(mesh_handle) else {
                 continue;
             };
 
-            let key = MeshPipelineKey::from_primitive_topology(mesh.primitive_topology) | view_key;
+            let hull = hull_layers.contains(&outline.layer);
+            let key = StencilPipelineKey {
+                mesh_key: MeshPipelineKey::from_primitive_topology(mesh.primitive_topology)
+                    | view_key,
+                alpha_mask: alpha_mask.is_some() && !hull,
+                hull,
+            };
 
             let Ok(pipeline) = pipelines.specialize(&pipeline_cache, &stencil_pipeline, key, &mesh.layout) else {
                 continue;
@@ -299,9 +777,70 @@ pub fn queue_mesh_stencil(
             stencil_phase.add(MeshStencil {
                 entity,
                 pipeline,
-                draw_function: draw_mesh_stencil,
+                draw_function: match (hull, alpha_mask) {
+                    (true, _) => draw_mesh_hull,
+                    (false, Some(_)) => draw_mesh_stencil_alpha_mask,
+                    (false, None) => draw_mesh_stencil,
+                },
                 distance: inv_view_row_2.dot(mesh_uniform.transform.col(3)),
+                layer: outline.layer,
             });
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn overlap_tiers_are_ordered() {
+        let priority = |priority| overlap_tier(OutlineOverlap::Priority(priority));
+        let nearest = overlap_tier(OutlineOverlap::Nearest);
+        assert!(overlap_tier(OutlineOverlap::Blend) < nearest);
+        assert!(nearest < priority(i32::MIN));
+        assert!(priority(-1) < priority(0));
+        assert!(priority(0) < priority(1));
+    }
+
+    #[test]
+    fn overlap_tiers_fit_in_rank_texture() {
+        // Integers up to 2048 are stored exactly in a half float
+        let max = overlap_tier(OutlineOverlap::Priority(i32::MAX));
+        assert!(max < 2048.0);
+        assert_eq!(max.fract(), 0.0);
+    }
+
+    #[test]
+    fn groups_fit_in_rank_texture() {
+        let [first, second] = [OutlineLayer(0), OutlineLayer(1)];
+        let (indices, merged) = group_indices(
+            [
+                (first, 2048),
+                (first, 0),
+                (second, u32::MAX),
+                (first, 2048),
+            ]
+            .into_iter(),
+        );
+        assert!(!merged);
+        // The groups 0 and 2048 aren't outlined together
+        assert_eq!(indices[&(first, 0)], 0.0);
+        assert_eq!(indices[&(first, 2048)], 1.0);
+        // Each layer numbers its own groups
+        assert_eq!(indices[&(second, u32::MAX)], 0.0);
+    }
+
+    #[test]
+    fn extra_groups_are_reported() {
+        let layer = OutlineLayer(0);
+        let groups = (0..GROUP_COUNT as u32 + 2).map(|group| (layer, group * 3));
+        let (indices, merged) = group_indices(groups);
+        assert!(merged);
+        let last = (GROUP_COUNT - 1) as f32;
+        // Every index is stored exactly in the rank texture
+        assert!(last < 2048.0);
+        assert_eq!(indices[&(layer, (GROUP_COUNT as u32 - 1) * 3)], last);
+        assert_eq!(indices[&(layer, (GROUP_COUNT as u32 + 1) * 3)], last);
+    }
+}
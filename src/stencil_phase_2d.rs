@@ -0,0 +1,561 @@
+use bevy::{
+    asset::load_internal_asset,
+    core_pipeline::core_2d::Camera2d,
+    ecs::{
+        query::ROQueryItem,
+        system::{
+            lifetimeless::{Read, SRes},
+            SystemParamItem,
+        },
+    },
+    prelude::*,
+    reflect::TypeUuid,
+    render::{
+        extract_component::{ComponentUniforms, DynamicUniformIndex, UniformComponentPlugin},
+        mesh::MeshVertexBufferLayout,
+        render_asset::RenderAssets,
+        render_phase::{
+            sort_phase_system, AddRenderCommand, CachedRenderPipelinePhaseItem, DrawFunctionId,
+            DrawFunctions, PhaseItem, RenderCommand, RenderCommandResult, RenderPhase,
+            SetItemPipeline, TrackedRenderPass,
+        },
+        render_resource::{
+            BindGroup, BindGroupDescriptor, BindGroupLayout, BindGroupLayoutDescriptor,
+            BindingResource, BindingType, BufferBindingType, CachedRenderPipelineId,
+            MultisampleState, PipelineCache, PrimitiveState, RenderPipelineDescriptor,
+            SamplerBindingType, ShaderType, SpecializedMeshPipeline, SpecializedMeshPipelineError,
+            SpecializedMeshPipelines, SpecializedRenderPipeline, SpecializedRenderPipelines,
+            TextureSampleType, TextureViewDimension, VertexState,
+        },
+        renderer::RenderDevice,
+        view::{ExtractedView, VisibleEntities},
+        Extract, RenderApp, RenderSet,
+    },
+    sprite::{
+        DrawMesh2d, Mesh2dHandle, Mesh2dPipeline, Mesh2dPipelineKey, Mesh2dUniform,
+        SetMesh2dBindGroup, SetMesh2dViewBindGroup,
+    },
+    utils::{FloatOrd, HashMap},
+};
+
+use crate::{
+    bind_group_entries, bind_group_layout_entries,
+    stencil_phase::{
+        stencil_depth_state, stencil_targets, SetStencilBindGroup, StencilPhaseItem,
+        StencilPipeline,
+    },
+    transition::OutlineTransitionState,
+    utils::fragment_state,
+    Outline, OutlineLayer,
+};
+
+pub const STENCIL_2D_SHADER_HANDLE: HandleUntyped =
+    HandleUntyped::weak_from_u64(Shader::TYPE_UUID, 6418335409727012367);
+
+/// Draws the stencil of 2d meshes and sprites with an [`Outline`] for every [`Camera2d`]
+pub struct Stencil2dPlugin;
+impl Plugin for Stencil2dPlugin {
+    fn build(&self, app: &mut App) {
+        load_internal_asset!(
+            app,
+            STENCIL_2D_SHADER_HANDLE,
+            "stencil_2d.wgsl",
+            Shader::from_wgsl
+        );
+        app.add_plugin(UniformComponentPlugin::<SpriteStencilUniform>::default());
+
+        let Ok(render_app) = app.get_sub_app_mut(RenderApp) else {
+            return;
+        };
+
+        render_app
+            .init_resource::<Mesh2dStencilPipeline>()
+            .init_resource::<SpecializedMeshPipelines<Mesh2dStencilPipeline>>()
+            .init_resource::<SpriteStencilPipeline>()
+            .init_resource::<SpecializedRenderPipelines<SpriteStencilPipeline>>()
+            .init_resource::<SpriteStencilBindGroups>()
+            .init_resource::<DrawFunctions<Stencil2d>>()
+            .add_render_command::<Stencil2d, DrawMesh2dStencil>()
+            .add_render_command::<Stencil2d, DrawSpriteStencil>()
+            .add_system(sort_phase_system::<Stencil2d>.in_set(RenderSet::PhaseSort))
+            .add_systems(
+                (extract_stencil_2d_phase, extract_sprite_stencils).in_schedule(ExtractSchedule),
+            )
+            .add_system(queue_sprite_stencil_bind_groups.in_set(RenderSet::Queue))
+            .add_system(queue_stencil_2d.in_set(RenderSet::Queue));
+    }
+}
+
+pub struct Stencil2d {
+    pub sort_key: FloatOrd,
+    pub pipeline: CachedRenderPipelineId,
+    pub entity: Entity,
+    pub draw_function: DrawFunctionId,
+    pub layer: OutlineLayer,
+}
+
+impl PhaseItem for Stencil2d {
+    type SortKey = FloatOrd;
+
+    fn entity(&self) -> Entity {
+        self.entity
+    }
+
+    fn sort_key(&self) -> Self::SortKey {
+        self.sort_key
+    }
+
+    fn draw_function(&self) -> DrawFunctionId {
+        self.draw_function
+    }
+}
+
+impl StencilPhaseItem for Stencil2d {
+    fn layer(&self) -> OutlineLayer {
+        self.layer
+    }
+}
+
+impl CachedRenderPipelinePhaseItem for Stencil2d {
+    fn cached_pipeline(&self) -> CachedRenderPipelineId {
+        self.pipeline
+    }
+}
+
+pub(crate) type DrawMesh2dStencil = (
+    SetItemPipeline,
+    SetMesh2dViewBindGroup<0>,
+    SetStencilBindGroup<1>,
+    SetMesh2dBindGroup<2>,
+    DrawMesh2d,
+);
+
+pub(crate) type DrawSpriteStencil = (
+    SetItemPipeline,
+    SetMesh2dViewBindGroup<0>,
+    SetStencilBindGroup<1>,
+    SetSpriteStencilBindGroup<2>,
+    DrawSpriteQuad,
+);
+
+#[derive(Resource)]
+pub struct Mesh2dStencilPipeline {
+    mesh2d_pipeline: Mesh2dPipeline,
+    stencil_bind_group_layout: BindGroupLayout,
+}
+
+impl FromWorld for Mesh2dStencilPipeline {
+    fn from_world(world: &mut World) -> Self {
+        Mesh2dStencilPipeline {
+            mesh2d_pipeline: world.resource::<Mesh2dPipeline>().clone(),
+            stencil_bind_group_layout: world
+                .resource::<StencilPipeline>()
+                .stencil_bind_group_layout
+                .clone(),
+        }
+    }
+}
+
+impl SpecializedMeshPipeline for Mesh2dStencilPipeline {
+    type Key = Mesh2dPipelineKey;
+
+    fn specialize(
+        &self,
+        key: Self::Key,
+        layout: &MeshVertexBufferLayout,
+    ) -> Result<RenderPipelineDescriptor, SpecializedMeshPipelineError> {
+        let mut desc = self.mesh2d_pipeline.specialize(key, layout)?;
+
+        desc.label = Some("mesh2d_stencil_pipeline".into());
+        desc.layout = vec![
+            self.mesh2d_pipeline.view_layout.clone(),
+            self.stencil_bind_group_layout.clone(),
+            self.mesh2d_pipeline.mesh_layout.clone(),
+        ];
+        desc.vertex.shader = STENCIL_2D_SHADER_HANDLE.typed::<Shader>();
+        desc.fragment = fragment_state(
+            STENCIL_2D_SHADER_HANDLE,
+            "fragment",
+            &stencil_targets(key.contains(Mesh2dPipelineKey::HDR)),
+            &desc.vertex.shader_defs,
+        );
+        desc.depth_stencil = Some(stencil_depth_state());
+
+        Ok(desc)
+    }
+}
+
+/// The quad of a sprite and the part of its image it displays.
+///
+/// The quad is generated in the vertex shader, this only needs to be extracted for sprites with an [`Outline`].
+#[derive(Component, ShaderType, Clone)]
+pub struct SpriteStencilUniform {
+    /// Transforms the unit quad to the world position of the sprite
+    transform: Mat4,
+    /// uv of the bottom left corner of the quad in xy and of the top right corner in zw
+    uv_rect: Vec4,
+    alpha: f32,
+}
+
+#[derive(Component)]
+pub struct SpriteStencil {
+    image: Handle<Image>,
+}
+
+#[derive(Resource)]
+pub struct SpriteStencilPipeline {
+    view_layout: BindGroupLayout,
+    stencil_bind_group_layout: BindGroupLayout,
+    sprite_bind_group_layout: BindGroupLayout,
+}
+
+impl FromWorld for SpriteStencilPipeline {
+    fn from_world(world: &mut World) -> Self {
+        let render_device = world.resource::<RenderDevice>();
+
+        let sprite_bind_group_layout =
+            render_device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+                label: Some("sprite_stencil_bind_group_layout"),
+                entries: &bind_group_layout_entries![
+                    // sprite texture
+                    0 => BindingType::Texture {
+                        sample_type: TextureSampleType::Float { filterable: true },
+                        view_dimension: TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    // sprite sampler
+                    1 => BindingType::Sampler(SamplerBindingType::Filtering),
+                    // quad
+                    2 => BindingType::Buffer {
+                        ty: BufferBindingType::Uniform,
+                        has_dynamic_offset: true,
+                        min_binding_size: Some(SpriteStencilUniform::min_size()),
+                    },
+                ],
+            });
+
+        SpriteStencilPipeline {
+            view_layout: world.resource::<Mesh2dPipeline>().view_layout.clone(),
+            stencil_bind_group_layout: world
+                .resource::<StencilPipeline>()
+                .stencil_bind_group_layout
+                .clone(),
+            sprite_bind_group_layout,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SpriteStencilPipelineKey {
+    msaa_samples: u32,
+    hdr: bool,
+}
+
+impl SpecializedRenderPipeline for SpriteStencilPipeline {
+    type Key = SpriteStencilPipelineKey;
+
+    fn specialize(&self, key: Self::Key) -> RenderPipelineDescriptor {
+        let shader_defs = vec!["SPRITE".into()];
+        RenderPipelineDescriptor {
+            label: Some("sprite_stencil_pipeline".into()),
+            layout: vec![
+                self.view_layout.clone(),
+                self.stencil_bind_group_layout.clone(),
+                self.sprite_bind_group_layout.clone(),
+            ],
+            vertex: VertexState {
+                shader: STENCIL_2D_SHADER_HANDLE.typed::<Shader>(),
+                shader_defs: shader_defs.clone(),
+                entry_point: "sprite_vertex".into(),
+                buffers: vec![],
+            },
+            fragment: fragment_state(
+                STENCIL_2D_SHADER_HANDLE,
+                "fragment",
+                &stencil_targets(key.hdr),
+                &shader_defs,
+            ),
+            primitive: PrimitiveState::default(),
+            depth_stencil: Some(stencil_depth_state()),
+            multisample: MultisampleState {
+                count: key.msaa_samples,
+                ..default()
+            },
+            push_constant_ranges: vec![],
+        }
+    }
+}
+
+/// Bind groups of the sprite textures, recreated every frame since the uniform buffer can change
+#[derive(Resource, Default)]
+pub struct SpriteStencilBindGroups {
+    values: HashMap<Handle<Image>, BindGroup>,
+}
+
+pub struct SetSpriteStencilBindGroup<const I: usize>;
+impl<P: PhaseItem, const I: usize> RenderCommand<P> for SetSpriteStencilBindGroup<I> {
+    type Param = SRes<SpriteStencilBindGroups>;
+    type ViewWorldQuery = ();
+    type ItemWorldQuery = (
+        Read<SpriteStencil>,
+        Read<DynamicUniformIndex<SpriteStencilUniform>>,
+    );
+
+    #[inline]
+    fn render<'w>(
+        _item: &P,
+        _view: (),
+        (sprite, sprite_index): ROQueryItem<'w, Self::ItemWorldQuery>,
+        bind_groups: SystemParamItem<'w, '_, Self::Param>,
+        pass: &mut TrackedRenderPass<'w>,
+    ) -> RenderCommandResult {
+        let Some(bind_group) = bind_groups.into_inner().values.get(&sprite.image) else {
+            return RenderCommandResult::Failure;
+        };
+        pass.set_bind_group(I, bind_group, &[sprite_index.index()]);
+        RenderCommandResult::Success
+    }
+}
+
+pub struct DrawSpriteQuad;
+impl<P: PhaseItem> RenderCommand<P> for DrawSpriteQuad {
+    type Param = ();
+    type ViewWorldQuery = ();
+    type ItemWorldQuery = ();
+
+    #[inline]
+    fn render<'w>(
+        _item: &P,
+        _view: (),
+        _entity: (),
+        _param: (),
+        pass: &mut TrackedRenderPass<'w>,
+    ) -> RenderCommandResult {
+        pass.draw(0..6, 0..1);
+        RenderCommandResult::Success
+    }
+}
+
+/// Make sure all 2d cameras have a [`Stencil2d`] [`RenderPhase`]
+pub fn extract_stencil_2d_phase(
+    mut commands: Commands,
+    cameras: Extract<Query<Entity, With<Camera2d>>>,
+) {
+    for entity in cameras.iter() {
+        commands
+            .get_or_spawn(entity)
+            .insert(RenderPhase::<Stencil2d>::default());
+    }
+}
+
+/// Computes the quad of every visible sprite with an [`Outline`], this mirrors what `bevy_sprite` does
+pub fn extract_sprite_stencils(
+    mut commands: Commands,
+    images: Extract<Res<Assets<Image>>>,
+    texture_atlases: Extract<Res<Assets<TextureAtlas>>>,
+    sprites: Extract<
+        Query<
+            (
+                Entity,
+                &ComputedVisibility,
+                &Sprite,
+                &GlobalTransform,
+                &Handle<Image>,
+            ),
+            Or<(With<Outline>, With<OutlineTransitionState>)>,
+        >,
+    >,
+    atlas_sprites: Extract<
+        Query<
+            (
+                Entity,
+                &ComputedVisibility,
+                &TextureAtlasSprite,
+                &GlobalTransform,
+                &Handle<TextureAtlas>,
+            ),
+            Or<(With<Outline>, With<OutlineTransitionState>)>,
+        >,
+    >,
+) {
+    let mut extract = |entity: Entity,
+                       transform: &GlobalTransform,
+                       image: &Handle<Image>,
+                       rect: Option<Rect>,
+                       custom_size: Option<Vec2>,
+                       flip: (bool, bool),
+                       anchor: Vec2,
+                       alpha: f32| {
+        let Some(image_size) = images.get(image).map(|image| image.size()) else {
+            return;
+        };
+        let rect = rect.unwrap_or(Rect::from_corners(Vec2::ZERO, image_size));
+        let size = custom_size.unwrap_or(rect.size());
+
+        // The uv of the image is flipped vertically compared to the quad
+        let mut bottom_left = Vec2::new(0.0, 1.0);
+        let mut top_right = Vec2::new(1.0, 0.0);
+        if flip.0 {
+            std::mem::swap(&mut bottom_left.x, &mut top_right.x);
+        }
+        if flip.1 {
+            std::mem::swap(&mut bottom_left.y, &mut top_right.y);
+        }
+        let to_image_uv = |uv: Vec2| (rect.min + uv * rect.size()) / image_size;
+        let (bottom_left, top_right) = (to_image_uv(bottom_left), to_image_uv(top_right));
+
+        let quad = Mat4::from_scale_rotation_translation(
+            size.extend(1.0),
+            Quat::IDENTITY,
+            (-(anchor + 0.5) * size).extend(0.0),
+        );
+        commands.get_or_spawn(entity).insert((
+            SpriteStencilUniform {
+                transform: transform.compute_matrix() * quad,
+                uv_rect: Vec4::new(bottom_left.x, bottom_left.y, top_right.x, top_right.y),
+                alpha,
+            },
+            SpriteStencil {
+                image: image.clone_weak(),
+            },
+        ));
+    };
+
+    for (entity, visibility, sprite, transform, image) in &sprites {
+        if !visibility.is_visible() {
+            continue;
+        }
+        extract(
+            entity,
+            transform,
+            image,
+            sprite.rect,
+            sprite.custom_size,
+            (sprite.flip_x, sprite.flip_y),
+            sprite.anchor.as_vec(),
+            sprite.color.a(),
+        );
+    }
+    for (entity, visibility, sprite, transform, atlas) in &atlas_sprites {
+        if !visibility.is_visible() {
+            continue;
+        }
+        let Some(atlas) = texture_atlases.get(atlas) else {
+            continue;
+        };
+        extract(
+            entity,
+            transform,
+            &atlas.texture,
+            Some(atlas.textures[sprite.index]),
+            sprite.custom_size,
+            (sprite.flip_x, sprite.flip_y),
+            sprite.anchor.as_vec(),
+            sprite.color.a(),
+        );
+    }
+}
+
+/// Creates a bind group for every image used by a sprite with an [`Outline`]
+pub fn queue_sprite_stencil_bind_groups(
+    mut bind_groups: ResMut<SpriteStencilBindGroups>,
+    sprite_stencil_pipeline: Res<SpriteStencilPipeline>,
+    render_device: Res<RenderDevice>,
+    gpu_images: Res<RenderAssets<Image>>,
+    uniforms: Res<ComponentUniforms<SpriteStencilUniform>>,
+    sprites: Query<&SpriteStencil>,
+) {
+    bind_groups.values.clear();
+    let Some(uniform) = uniforms.binding() else {
+        return;
+    };
+
+    for sprite in &sprites {
+        if bind_groups.values.contains_key(&sprite.image) {
+            continue;
+        }
+        let Some(gpu_image) = gpu_images.get(&sprite.image) else {
+            continue;
+        };
+        let bind_group = render_device.create_bind_group(&BindGroupDescriptor {
+            label: Some("sprite_stencil_bind_group"),
+            layout: &sprite_stencil_pipeline.sprite_bind_group_layout,
+            entries: &bind_group_entries![
+                0 => BindingResource::TextureView(&gpu_image.texture_view),
+                1 => BindingResource::Sampler(&gpu_image.sampler),
+                2 => uniform.clone(),
+            ],
+        });
+        bind_groups
+            .values
+            .insert(sprite.image.clone_weak(), bind_group);
+    }
+}
+
+/// Add any visible 2d mesh or sprite with an [`Outline`] to the 2d stencil phase
+pub fn queue_stencil_2d(
+    stencil_draw_functions: Res<DrawFunctions<Stencil2d>>,
+    mesh2d_stencil_pipeline: Res<Mesh2dStencilPipeline>,
+    mut mesh2d_pipelines: ResMut<SpecializedMeshPipelines<Mesh2dStencilPipeline>>,
+    sprite_stencil_pipeline: Res<SpriteStencilPipeline>,
+    mut sprite_pipelines: ResMut<SpecializedRenderPipelines<SpriteStencilPipeline>>,
+    pipeline_cache: Res<PipelineCache>,
+    render_meshes: Res<RenderAssets<Mesh>>,
+    outline_meshes: Query<(&Outline, &Mesh2dHandle, &Mesh2dUniform)>,
+    outline_sprites: Query<(&Outline, &SpriteStencilUniform)>,
+    mut views: Query<(&ExtractedView, &VisibleEntities, &mut RenderPhase<Stencil2d>)>,
+    msaa: Res<Msaa>,
+) {
+    let draw_functions = stencil_draw_functions.read();
+    let draw_mesh2d_stencil = draw_functions.get_id::<DrawMesh2dStencil>().unwrap();
+    let draw_sprite_stencil = draw_functions.get_id::<DrawSpriteStencil>().unwrap();
+
+    for (view, visible_entities, mut stencil_phase) in views.iter_mut() {
+        // The colors of the stencil are stored in the format of the view
+        let sprite_pipeline = sprite_pipelines.specialize(
+            &pipeline_cache,
+            &sprite_stencil_pipeline,
+            SpriteStencilPipelineKey {
+                msaa_samples: msaa.samples(),
+                hdr: view.hdr,
+            },
+        );
+        let view_key = Mesh2dPipelineKey::from_msaa_samples(msaa.samples())
+            | Mesh2dPipelineKey::from_hdr(view.hdr);
+
+        for visible_entity in visible_entities.entities.iter().copied() {
+            if let Ok((outline, sprite)) = outline_sprites.get(visible_entity) {
+                stencil_phase.add(Stencil2d {
+                    entity: visible_entity,
+                    pipeline: sprite_pipeline,
+                    draw_function: draw_sprite_stencil,
+                    sort_key: FloatOrd(sprite.transform.w_axis.z),
+                    layer: outline.layer,
+                });
+                continue;
+            }
+
+            let Ok((outline, mesh_handle, mesh_uniform)) = outline_meshes.get(visible_entity) else {
+                continue;
+            };
+            let Some(mesh) = render_meshes.get(&mesh_handle.0) else {
+                continue;
+            };
+
+            let key =
+                Mesh2dPipelineKey::from_primitive_topology(mesh.primitive_topology) | view_key;
+
+            let Ok(pipeline) = mesh2d_pipelines.specialize(&pipeline_cache, &mesh2d_stencil_pipeline, key, &mesh.layout) else {
+                continue;
+            };
+
+            stencil_phase.add(Stencil2d {
+                entity: visible_entity,
+                pipeline,
+                draw_function: draw_mesh2d_stencil,
+                sort_key: FloatOrd(mesh_uniform.transform.w_axis.z),
+                layer: outline.layer,
+            });
+        }
+    }
+}
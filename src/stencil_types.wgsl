@@ -0,0 +1,105 @@
+#define_import_path bevy_outline::stencil_types
+
+struct StencilUniform {
+    color: vec4<f32>,
+    width: f32,
+    occlusion: u32,
+    xray_color: vec4<f32>,
+    alpha_cutoff: f32,
+    base_alpha: f32,
+    pulse_amplitude: f32,
+    pulse_frequency: f32,
+    hue_speed: f32,
+    dash_length: f32,
+    dash_speed: f32,
+    overlap_tier: f32,
+    interior_lines: u32,
+    group: f32,
+    visibility: f32,
+};
+
+// The settings of the layer of the outline, see StencilLayerUniform in stencil_phase.rs
+struct StencilLayer {
+    size: f32,
+    pixels_per_unit: f32,
+    world_units: u32,
+    perspective: u32,
+    fade_start: f32,
+    fade_end: f32,
+    fade_width: u32,
+    fade_alpha: u32,
+};
+
+const TAU: f32 = 6.28318530718;
+
+// Must match OVERLAP_TIER_BLEND in stencil_phase.rs
+const OVERLAP_TIER_BLEND: f32 = 0.0;
+// Every tier is below this, it scales the rank to the range of the depth
+const MAX_OVERLAP_TIER: f32 = 2048.0;
+
+// Rotates the hue of the color around the grey axis
+fn rotate_hue(color: vec3<f32>, angle: f32) -> vec3<f32> {
+    let k = vec3(0.57735026919);
+    let cos_angle = cos(angle);
+    return color * cos_angle + cross(k, color) * sin(angle) + k * dot(k, color) * (1.0 - cos_angle);
+}
+
+// Applies the pulse and the hue cycling of the OutlineAnimation
+fn animate_color(stencil: StencilUniform, color: vec4<f32>, time: f32) -> vec4<f32> {
+    let rgb = max(rotate_hue(color.rgb, TAU * stencil.hue_speed * time), vec3(0.0));
+    let wave = 0.5 - 0.5 * cos(TAU * stencil.pulse_frequency * time);
+    // The whole color is scaled because the outlines are premultiplied
+    return vec4(rgb * color.a, color.a) * (1.0 - stencil.pulse_amplitude * wave) * stencil.visibility;
+}
+
+// 1 up to the start of the fade and 0 after its end, the depth is the distance to the camera
+fn distance_fade(layer: StencilLayer, distance: f32) -> f32 {
+    if layer.fade_end <= layer.fade_start {
+        return f32(distance <= layer.fade_start);
+    }
+    return 1.0 - smoothstep(layer.fade_start, layer.fade_end, distance);
+}
+
+// Scales the width of the outline when the layer fades it
+fn width_fade(layer: StencilLayer, distance: f32) -> f32 {
+    return select(1.0, distance_fade(layer, distance), layer.fade_width != 0u);
+}
+
+// Scales the premultiplied color of the outline when the layer fades it
+fn alpha_fade(layer: StencilLayer, distance: f32) -> f32 {
+    return select(1.0, distance_fade(layer, distance), layer.fade_alpha != 0u);
+}
+
+// Width of the outline in pixels, world units get thinner with the view depth of the fragment.
+// Outlines fading in and out with an OutlineTransition are thinner too.
+fn pixel_width(stencil: StencilUniform, layer: StencilLayer, view_depth: f32) -> f32 {
+    let width = min(stencil.width, layer.size) * stencil.visibility * layer.pixels_per_unit;
+    if layer.world_units != 0u && layer.perspective != 0u {
+        return width / view_depth;
+    }
+    return width;
+}
+
+// The per pixel data used by the passes after the stencil, the width is in pixels.
+// The alpha is the coverage, the color can't be used for it since it can be black or transparent.
+fn stencil_data(stencil: StencilUniform, width: f32) -> vec4<f32> {
+    return vec4(width, stencil.dash_length, stencil.dash_speed, 1.0);
+}
+
+// The rank used to pick a single outline where outlines overlap, the highest tier wins then the nearest one.
+// Blended outlines don't use their depth, this way they are all mixed together.
+// The blue channel isn't part of the rank, it flags the outlines with interior lines.
+// The alpha channel isn't part of it either, it's the group of the outline.
+fn overlap_rank(stencil: StencilUniform, depth: f32) -> vec4<f32> {
+    let interior_lines = f32(stencil.interior_lines);
+    if stencil.overlap_tier == OVERLAP_TIER_BLEND {
+        return vec4(0.0, 0.0, interior_lines, stencil.group);
+    }
+    return vec4(stencil.overlap_tier, depth, interior_lines, stencil.group);
+}
+
+// The depth test of the stencil pass keeps the highest rank.
+// The depth is in the range [0, 1] so it can't move the rank to the next tier.
+fn overlap_depth(rank: vec4<f32>) -> f32 {
+    return (rank.x + rank.y * 0.5) / MAX_OVERLAP_TIER;
+}
@@ -0,0 +1,282 @@
+use bevy::prelude::*;
+
+use crate::{Outline, OutlineTransition};
+
+/// An outline as it's drawn during an [`OutlineTransition`]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DisplayedOutline {
+    pub outline: Outline,
+    /// Scales the width and the color of the outline, 0 hides it
+    pub visibility: f32,
+}
+
+impl DisplayedOutline {
+    fn visible(outline: Outline) -> Self {
+        Self {
+            outline,
+            visibility: 1.0,
+        }
+    }
+
+    fn hidden(outline: Outline) -> Self {
+        Self {
+            outline,
+            visibility: 0.0,
+        }
+    }
+
+    /// The color and the width move towards `to`, the other fields are the ones of `to`.
+    ///
+    /// The width only moves when both widths are set, `None` is the size of the layer which depends on the camera.
+    fn lerp(&self, to: &Self, t: f32) -> Self {
+        // The color ends in the space it was given in
+        if t >= 1.0 {
+            return *to;
+        }
+        let color = match self.outline.color == to.outline.color {
+            true => to.outline.color,
+            false => {
+                let from = Vec4::from(self.outline.color.as_linear_rgba_f32());
+                let color = from.lerp(Vec4::from(to.outline.color.as_linear_rgba_f32()), t);
+                Color::rgba_linear(color.x, color.y, color.z, color.w)
+            }
+        };
+        let width = match (self.outline.width, to.outline.width) {
+            (Some(from), Some(to)) => Some(from + (to - from) * t),
+            (_, width) => width,
+        };
+        Self {
+            outline: Outline {
+                color,
+                width,
+                ..to.outline
+            },
+            visibility: self.visibility + (to.visibility - self.visibility) * t,
+        }
+    }
+}
+
+/// The progress of the [`OutlineTransition`] of an entity.
+///
+/// It outlives the [`Outline`] of the entity until it has faded out.
+#[derive(Component, Clone, Copy, Debug)]
+pub struct OutlineTransitionState {
+    /// The outline the transition ends with, `None` while fading out
+    target: Option<Outline>,
+    from: DisplayedOutline,
+    to: DisplayedOutline,
+    elapsed: f32,
+    displayed: DisplayedOutline,
+}
+
+/// The outline to draw for an entity, if any
+pub fn displayed_outline(
+    outline: Option<&Outline>,
+    state: Option<&OutlineTransitionState>,
+) -> Option<DisplayedOutline> {
+    match state {
+        Some(state) => Some(state.displayed),
+        None => outline.copied().map(DisplayedOutline::visible),
+    }
+}
+
+/// Restarts the transitions of the outlines that changed and advances all of them
+pub fn animate_outline_transitions(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut transitions: Query<(
+        Entity,
+        &OutlineTransition,
+        Option<&Outline>,
+        Option<&mut OutlineTransitionState>,
+    )>,
+    states: Query<Entity, (With<OutlineTransitionState>, Without<OutlineTransition>)>,
+) {
+    // Without a transition the outline switches instantly again
+    for entity in &states {
+        commands.entity(entity).remove::<OutlineTransitionState>();
+    }
+
+    for (entity, transition, outline, state) in &mut transitions {
+        let target = outline.copied();
+        let mut new_state = match state.as_deref() {
+            Some(state) if state.target == target => *state,
+            // The transition starts from what is drawn now
+            Some(state) => OutlineTransitionState {
+                target,
+                from: state.displayed,
+                to: target.map_or(
+                    DisplayedOutline::hidden(state.displayed.outline),
+                    DisplayedOutline::visible,
+                ),
+                elapsed: 0.0,
+                displayed: state.displayed,
+            },
+            // A new outline fades in
+            None => {
+                let Some(outline) = target else {
+                    continue;
+                };
+                OutlineTransitionState {
+                    target,
+                    from: DisplayedOutline::hidden(outline),
+                    to: DisplayedOutline::visible(outline),
+                    elapsed: 0.0,
+                    displayed: DisplayedOutline::hidden(outline),
+                }
+            }
+        };
+
+        new_state.elapsed += time.delta_seconds();
+        let progress = match transition.duration > 0.0 {
+            true => new_state.elapsed / transition.duration,
+            false => 1.0,
+        };
+        new_state.displayed = new_state
+            .from
+            .lerp(&new_state.to, transition.easing.ease(progress));
+
+        if progress >= 1.0 && target.is_none() {
+            commands.entity(entity).remove::<OutlineTransitionState>();
+            continue;
+        }
+        match state {
+            Some(mut state) => {
+                // Finished transitions aren't marked as changed every frame
+                if state.displayed != new_state.displayed || state.target != new_state.target {
+                    *state = new_state;
+                }
+            }
+            None => {
+                commands.entity(entity).insert(new_state);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::OutlineEasing;
+
+    fn outline(color: Color) -> Outline {
+        Outline { color, ..default() }
+    }
+
+    fn world() -> World {
+        let mut world = World::new();
+        let mut time = Time::default();
+        // The first update doesn't have a delta
+        time.update_with_instant(time.startup());
+        world.insert_resource(time);
+        world
+    }
+
+    /// Advances the time by a tenth of a second
+    fn update(world: &mut World) {
+        let mut time = world.resource_mut::<Time>();
+        let last_update = time.last_update().unwrap();
+        time.update_with_instant(last_update + Duration::from_millis(100));
+        let mut schedule = Schedule::new();
+        schedule.add_system(animate_outline_transitions);
+        schedule.run(world);
+    }
+
+    fn displayed(world: &World, entity: Entity) -> Option<DisplayedOutline> {
+        displayed_outline(
+            world.get::<Outline>(entity),
+            world.get::<OutlineTransitionState>(entity),
+        )
+    }
+
+    #[test]
+    fn easings_start_at_0_and_end_at_1() {
+        for easing in [
+            OutlineEasing::Linear,
+            OutlineEasing::EaseIn,
+            OutlineEasing::EaseOut,
+            OutlineEasing::EaseInOut,
+        ] {
+            assert_eq!(easing.ease(0.0), 0.0);
+            assert_eq!(easing.ease(1.0), 1.0);
+            assert_eq!(easing.ease(2.0), 1.0);
+            assert!(easing.ease(0.25) < easing.ease(0.75));
+        }
+        assert!(OutlineEasing::EaseIn.ease(0.5) < 0.5);
+        assert!(OutlineEasing::EaseOut.ease(0.5) > 0.5);
+    }
+
+    #[test]
+    fn fades_in_and_out() {
+        let mut world = world();
+        let transition = OutlineTransition {
+            duration: 0.4,
+            easing: OutlineEasing::Linear,
+        };
+        let entity = world.spawn((outline(Color::RED), transition)).id();
+        let visibility = |world: &World| displayed(world, entity).map(|d| d.visibility);
+
+        update(&mut world);
+        assert!((visibility(&world).unwrap() - 0.25).abs() < 1e-4);
+        for _ in 0..3 {
+            update(&mut world);
+        }
+        assert_eq!(visibility(&world), Some(1.0));
+
+        // The outline is still drawn after it's removed, until it has faded out
+        world.entity_mut(entity).remove::<Outline>();
+        update(&mut world);
+        assert!((visibility(&world).unwrap() - 0.75).abs() < 1e-4);
+        assert_eq!(
+            displayed(&world, entity).unwrap().outline,
+            outline(Color::RED)
+        );
+        for _ in 0..3 {
+            update(&mut world);
+        }
+        assert_eq!(visibility(&world), None);
+        assert!(world.get::<OutlineTransitionState>(entity).is_none());
+    }
+
+    #[test]
+    fn interpolates_changes() {
+        let mut world = world();
+        let transition = OutlineTransition {
+            duration: 0.2,
+            easing: OutlineEasing::Linear,
+        };
+        let from = Outline {
+            color: Color::BLACK,
+            width: Some(2.0),
+            ..default()
+        };
+        let entity = world.spawn((from, transition)).id();
+        update(&mut world);
+        update(&mut world);
+        assert_eq!(displayed(&world, entity).unwrap().outline, from);
+
+        let to = Outline {
+            color: Color::WHITE,
+            width: Some(4.0),
+            ..default()
+        };
+        world.entity_mut(entity).insert(to);
+        update(&mut world);
+        let halfway = displayed(&world, entity).unwrap();
+        assert_eq!(halfway.visibility, 1.0);
+        assert!((halfway.outline.width.unwrap() - 3.0).abs() < 1e-4);
+        assert!((halfway.outline.color.as_linear_rgba_f32()[0] - 0.5).abs() < 1e-4);
+        update(&mut world);
+        assert_eq!(displayed(&world, entity).unwrap().outline, to);
+
+        // The size of the layer isn't known, the width switches to it at the start
+        let layer_width = Outline { width: None, ..to };
+        world.entity_mut(entity).insert(layer_width);
+        update(&mut world);
+        let halfway = displayed(&world, entity).unwrap();
+        assert_eq!(halfway.outline.width, None);
+        assert_eq!(halfway.outline.color, to.color);
+    }
+}
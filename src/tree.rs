@@ -0,0 +1,346 @@
+use bevy::{prelude::*, sprite::Mesh2dHandle, utils::HashSet};
+
+use crate::{
+    transition::OutlineTransitionState, Outline, OutlineAnimation, OutlineOptOut, OutlineTransition,
+    OutlineTree,
+};
+
+/// Marks an [`Outline`] copied from an [`OutlineTree`], it's removed once no tree reaches the entity
+#[derive(Component)]
+pub struct InheritedOutline {
+    /// The [`OutlineAnimation`] was copied too
+    animation: bool,
+    /// The [`OutlineTransition`] was copied too
+    transition: bool,
+}
+
+/// Copies the outline of every [`OutlineTree`] to the meshes and sprites below it,
+/// and removes the copies from the entities that aren't reached anymore.
+///
+/// While the outline of a root fades out, the copies fade out with the copied [`OutlineTransition`].
+pub fn propagate_outline_trees(
+    mut commands: Commands,
+    roots: Query<
+        (
+            Entity,
+            Option<&Outline>,
+            Option<&OutlineAnimation>,
+            Option<&OutlineTransition>,
+        ),
+        (
+            With<OutlineTree>,
+            Without<InheritedOutline>,
+            Or<(With<Outline>, With<OutlineTransitionState>)>,
+        ),
+    >,
+    children: Query<&Children>,
+    nodes: Query<(
+        Option<&Outline>,
+        Option<&OutlineAnimation>,
+        Option<&OutlineTransition>,
+        Option<&InheritedOutline>,
+        Option<&OutlineOptOut>,
+    )>,
+    outlinable: Query<
+        (),
+        Or<(
+            With<Handle<Mesh>>,
+            With<Mesh2dHandle>,
+            With<Sprite>,
+            With<TextureAtlasSprite>,
+        )>,
+    >,
+    inherited: Query<(Entity, &InheritedOutline)>,
+    mut reached: Local<HashSet<Entity>>,
+) {
+    reached.clear();
+    for (root, outline, animation, transition) in &roots {
+        let mut stack = children
+            .get(root)
+            .map_or(Vec::new(), |children| children.to_vec());
+        while let Some(entity) = stack.pop() {
+            let Ok((own_outline, own_animation, own_transition, inherited, opt_out)) = nodes.get(entity) else {
+                continue;
+            };
+            // A nested tree outlines its own descendants
+            if opt_out.is_some() || roots.contains(entity) {
+                continue;
+            }
+            if let Ok(entity_children) = children.get(entity) {
+                stack.extend(entity_children.iter());
+            }
+            let explicit = own_outline.is_some() && inherited.is_none();
+            if explicit || !outlinable.contains(entity) {
+                continue;
+            }
+            let Some(outline) = outline else {
+                // The root is fading out, the copies are kept until they have faded out too
+                if inherited.is_some() {
+                    reached.insert(entity);
+                    if own_outline.is_some() {
+                        commands.entity(entity).remove::<Outline>();
+                    }
+                }
+                continue;
+            };
+            reached.insert(entity);
+
+            let inherits_animation = inherited.is_some_and(|inherited| inherited.animation);
+            let inherits_transition = inherited.is_some_and(|inherited| inherited.transition);
+            // An animation or a transition added to the entity itself is kept
+            let copied_animation =
+                animation.filter(|_| own_animation.is_none() || inherits_animation);
+            let copied_transition =
+                transition.filter(|_| own_transition.is_none() || inherits_transition);
+            let up_to_date = inherited.is_some()
+                && own_outline == Some(outline)
+                && inherits_animation == copied_animation.is_some()
+                && (copied_animation.is_none() || own_animation == copied_animation)
+                && inherits_transition == copied_transition.is_some()
+                && (copied_transition.is_none() || own_transition == copied_transition);
+            if up_to_date {
+                continue;
+            }
+            let mut entity_commands = commands.entity(entity);
+            entity_commands.insert((
+                *outline,
+                InheritedOutline {
+                    animation: copied_animation.is_some(),
+                    transition: copied_transition.is_some(),
+                },
+            ));
+            match copied_animation {
+                Some(animation) => {
+                    entity_commands.insert(*animation);
+                }
+                None if inherits_animation => {
+                    entity_commands.remove::<OutlineAnimation>();
+                }
+                None => {}
+            }
+            match copied_transition {
+                Some(transition) => {
+                    entity_commands.insert(*transition);
+                }
+                None if inherits_transition => {
+                    entity_commands.remove::<OutlineTransition>();
+                }
+                None => {}
+            }
+        }
+    }
+
+    for (entity, inherited) in &inherited {
+        if reached.contains(&entity) {
+            continue;
+        }
+        let mut entity_commands = commands.entity(entity);
+        entity_commands.remove::<(Outline, InheritedOutline)>();
+        if inherited.animation {
+            entity_commands.remove::<OutlineAnimation>();
+        }
+        if inherited.transition {
+            entity_commands.remove::<OutlineTransition>();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::{
+        transition::{animate_outline_transitions, displayed_outline},
+        OutlineEasing,
+    };
+
+    fn outline(color: Color) -> Outline {
+        Outline { color, ..default() }
+    }
+
+    fn update(world: &mut World) {
+        let mut schedule = Schedule::new();
+        schedule.add_system(propagate_outline_trees);
+        schedule.run(world);
+    }
+
+    fn spawn_mesh(world: &mut World) -> Entity {
+        world.spawn(Handle::<Mesh>::default()).id()
+    }
+
+    #[test]
+    fn outlines_descendant_meshes() {
+        let mut world = World::new();
+        let mesh = spawn_mesh(&mut world);
+        let node = world.spawn_empty().push_children(&[mesh]).id();
+        let root = world
+            .spawn((outline(Color::RED), OutlineTree))
+            .push_children(&[node])
+            .id();
+
+        update(&mut world);
+        assert_eq!(world.get::<Outline>(mesh), Some(&outline(Color::RED)));
+        // Only the entities that can be drawn get an outline
+        assert_eq!(world.get::<Outline>(node), None);
+
+        // Changes of the root are copied
+        world.get_mut::<Outline>(root).unwrap().color = Color::BLUE;
+        update(&mut world);
+        assert_eq!(world.get::<Outline>(mesh), Some(&outline(Color::BLUE)));
+
+        // Children spawned later are outlined too
+        let late_mesh = spawn_mesh(&mut world);
+        world.entity_mut(node).push_children(&[late_mesh]);
+        update(&mut world);
+        assert_eq!(world.get::<Outline>(late_mesh), Some(&outline(Color::BLUE)));
+
+        // Removing the tree removes the copies
+        world.entity_mut(root).remove::<OutlineTree>();
+        update(&mut world);
+        assert_eq!(world.get::<Outline>(mesh), None);
+        assert_eq!(world.get::<Outline>(late_mesh), None);
+        assert_eq!(world.get::<Outline>(root), Some(&outline(Color::BLUE)));
+    }
+
+    #[test]
+    fn copies_the_animation() {
+        let mut world = World::new();
+        let mesh = spawn_mesh(&mut world);
+        let animated_mesh = world
+            .spawn((
+                Handle::<Mesh>::default(),
+                OutlineAnimation {
+                    pulse_frequency: 2.0,
+                    ..default()
+                },
+            ))
+            .id();
+        let animation = OutlineAnimation {
+            hue_speed: 1.0,
+            ..default()
+        };
+        let root = world
+            .spawn((outline(Color::RED), animation, OutlineTree))
+            .push_children(&[mesh, animated_mesh])
+            .id();
+
+        update(&mut world);
+        assert_eq!(world.get::<OutlineAnimation>(mesh), Some(&animation));
+        // The animation of the entity itself is kept
+        assert_eq!(
+            world
+                .get::<OutlineAnimation>(animated_mesh)
+                .unwrap()
+                .pulse_frequency,
+            2.0
+        );
+
+        world.entity_mut(root).remove::<OutlineAnimation>();
+        update(&mut world);
+        assert_eq!(world.get::<OutlineAnimation>(mesh), None);
+        assert!(world.get::<OutlineAnimation>(animated_mesh).is_some());
+    }
+
+    #[test]
+    fn opt_out_and_own_outlines() {
+        let mut world = World::new();
+        let opted_out_mesh = spawn_mesh(&mut world);
+        let opted_out = world
+            .spawn((Handle::<Mesh>::default(), OutlineOptOut))
+            .push_children(&[opted_out_mesh])
+            .id();
+        let own_mesh = world
+            .spawn((Handle::<Mesh>::default(), outline(Color::GREEN)))
+            .id();
+        let nested_mesh = spawn_mesh(&mut world);
+        let nested = world
+            .spawn((outline(Color::BLUE), OutlineTree))
+            .push_children(&[nested_mesh])
+            .id();
+        let root = world
+            .spawn((outline(Color::RED), OutlineTree))
+            .push_children(&[opted_out, own_mesh, nested])
+            .id();
+
+        update(&mut world);
+        assert_eq!(world.get::<Outline>(opted_out), None);
+        assert_eq!(world.get::<Outline>(opted_out_mesh), None);
+        assert_eq!(world.get::<Outline>(own_mesh), Some(&outline(Color::GREEN)));
+        assert_eq!(
+            world.get::<Outline>(nested_mesh),
+            Some(&outline(Color::BLUE))
+        );
+
+        // The outlines that weren't copied are never removed
+        world.entity_mut(root).despawn();
+        update(&mut world);
+        assert_eq!(world.get::<Outline>(own_mesh), Some(&outline(Color::GREEN)));
+        assert_eq!(
+            world.get::<Outline>(nested_mesh),
+            Some(&outline(Color::BLUE))
+        );
+    }
+
+    #[test]
+    fn copies_fade_out_with_the_root() {
+        let mut world = World::new();
+        let mut time = Time::default();
+        // The first update doesn't have a delta
+        time.update_with_instant(time.startup());
+        world.insert_resource(time);
+        let mut schedule = Schedule::new();
+        schedule.add_systems(
+            (
+                propagate_outline_trees,
+                apply_system_buffers,
+                animate_outline_transitions,
+            )
+                .chain(),
+        );
+        let mut update = |world: &mut World| {
+            let mut time = world.resource_mut::<Time>();
+            let last_update = time.last_update().unwrap();
+            time.update_with_instant(last_update + Duration::from_millis(100));
+            schedule.run(world);
+        };
+        let visibility = |world: &World, entity: Entity| {
+            displayed_outline(
+                world.get::<Outline>(entity),
+                world.get::<OutlineTransitionState>(entity),
+            )
+            .map(|displayed| displayed.visibility)
+        };
+
+        let mesh = spawn_mesh(&mut world);
+        let transition = OutlineTransition {
+            duration: 0.4,
+            easing: OutlineEasing::Linear,
+        };
+        let root = world
+            .spawn((outline(Color::RED), transition, OutlineTree))
+            .push_children(&[mesh])
+            .id();
+        for _ in 0..4 {
+            update(&mut world);
+        }
+        assert_eq!(world.get::<OutlineTransition>(mesh), Some(&transition));
+        assert_eq!(visibility(&world, mesh), Some(1.0));
+
+        // The copy fades out instead of disappearing with the outline of the root
+        world.entity_mut(root).remove::<Outline>();
+        update(&mut world);
+        assert!((visibility(&world, mesh).unwrap() - 0.75).abs() < 1e-4);
+        assert_eq!(visibility(&world, mesh), visibility(&world, root));
+        update(&mut world);
+        assert!((visibility(&world, mesh).unwrap() - 0.5).abs() < 1e-4);
+
+        for _ in 0..3 {
+            update(&mut world);
+        }
+        assert_eq!(visibility(&world, mesh), None);
+        assert!(world.get::<InheritedOutline>(mesh).is_none());
+        assert!(world.get::<OutlineTransition>(mesh).is_none());
+        assert_eq!(world.get::<OutlineTransition>(root), Some(&transition));
+    }
+}
@@ -8,12 +8,22 @@ use bevy::{
             TextureFormat, VertexState,
         },
         texture::BevyDefault,
+        view::ViewTarget,
     },
 };
 
-pub fn color_target(blend: Option<BlendState>) -> ColorTargetState {
+/// Format of the textures storing the colors of the outlines.
+/// It matches the view so HDR views keep the colors above 1.0, which lets the outlines bloom.
+pub fn color_texture_format(hdr: bool) -> TextureFormat {
+    match hdr {
+        true => ViewTarget::TEXTURE_FORMAT_HDR,
+        false => TextureFormat::bevy_default(),
+    }
+}
+
+pub fn color_target(blend: Option<BlendState>, hdr: bool) -> ColorTargetState {
     ColorTargetState {
-        format: TextureFormat::bevy_default(),
+        format: color_texture_format(hdr),
         blend,
         write_mask: ColorWrites::ALL,
     }
@@ -0,0 +1,265 @@
+use bevy::prelude::*;
+
+use crate::{OutlineSettings, OutlineType};
+
+/// The part of the render target drawn by a camera, in physical pixels.
+///
+/// The outline textures only cover the viewport, the UVs of the passes drawing to them are relative to the viewport.
+/// The view target, its depth and the prepass textures cover the whole target, their texels are offset by the origin.
+#[derive(Component, Clone, Copy, Debug, PartialEq)]
+pub struct OutlineViewport {
+    pub origin: UVec2,
+    pub size: UVec2,
+    /// Physical pixels per logical pixel of the target
+    pub scale_factor: f32,
+}
+
+impl OutlineViewport {
+    /// `None` when the viewport is empty or doesn't fit inside the target, nothing can be drawn to it
+    pub fn new(origin: UVec2, size: UVec2, target_size: UVec2) -> Option<Self> {
+        if size.cmpeq(UVec2::ZERO).any() || (origin + size).cmpgt(target_size).any() {
+            return None;
+        }
+        Some(Self {
+            origin,
+            size,
+            scale_factor: 1.0,
+        })
+    }
+
+    /// The viewport of a window or an image camera, `None` until the size of its target is known
+    pub fn from_camera(camera: &Camera) -> Option<Self> {
+        let (origin, _) = camera.physical_viewport_rect()?;
+        let target_size = camera.physical_target_size()?;
+        Some(Self {
+            scale_factor: target_size.x as f32 / camera.logical_target_size()?.x,
+            ..Self::new(origin, camera.physical_viewport_size()?, target_size)?
+        })
+    }
+
+    /// Size of the textures the outlines of a layer are spread into, its inverse is the texel size used by their passes.
+    ///
+    /// The blurs can use a downsampled texture, the stencil and the other outlines cover the viewport.
+    pub fn outline_texture_size(&self, settings: &OutlineSettings) -> UVec2 {
+        match settings.outline_type {
+            OutlineType::BoxBlur | OutlineType::GaussianBlur => {
+                settings.downsample.texture_size(self.size)
+            }
+            OutlineType::MaxFilter
+            | OutlineType::Jfa
+            | OutlineType::InvertedHull
+            | OutlineType::EdgeDetect => self.size,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::{
+        render::{
+            camera::{camera_system, RenderTarget, Viewport},
+            render_resource::{Extent3d, TextureDimension, TextureFormat},
+        },
+        window::{PrimaryWindow, WindowCreated, WindowRef, WindowResized, WindowResolution},
+    };
+
+    use super::*;
+    use crate::OutlineDownsample;
+
+    fn window(width: f32, height: f32, scale_factor: f64) -> Window {
+        Window {
+            resolution: WindowResolution::new(width, height)
+                .with_scale_factor_override(scale_factor),
+            ..default()
+        }
+    }
+
+    /// A primary window of 1280x720 physical pixels
+    fn primary_window(scale_factor: f64) -> impl FnOnce(&mut App) -> RenderTarget {
+        move |app| {
+            app.world
+                .spawn((window(1280.0, 720.0, scale_factor), PrimaryWindow));
+            RenderTarget::Window(WindowRef::Primary)
+        }
+    }
+
+    /// The viewport of a camera drawing to the target created by `target`
+    fn camera_viewport(
+        viewport: Option<Viewport>,
+        target: impl FnOnce(&mut App) -> RenderTarget,
+    ) -> Option<OutlineViewport> {
+        let mut app = App::new();
+        app.add_plugin(AssetPlugin::default())
+            .add_asset::<Image>()
+            .add_event::<WindowCreated>()
+            .add_event::<WindowResized>()
+            .add_system(camera_system::<Projection>);
+        let target = target(&mut app);
+        let camera = app
+            .world
+            .spawn(Camera3dBundle {
+                camera: Camera {
+                    viewport,
+                    target,
+                    ..default()
+                },
+                ..default()
+            })
+            .id();
+        app.update();
+        OutlineViewport::from_camera(app.world.get::<Camera>(camera).unwrap())
+    }
+
+    /// The left and right halves of a split-screen window
+    fn split_screen(target: UVec2) -> [Viewport; 2] {
+        let left = target.x / 2;
+        [
+            Viewport {
+                physical_position: UVec2::ZERO,
+                physical_size: UVec2::new(left, target.y),
+                ..default()
+            },
+            Viewport {
+                physical_position: UVec2::new(left, 0),
+                physical_size: UVec2::new(target.x - left, target.y),
+                ..default()
+            },
+        ]
+    }
+
+    fn settings(outline_type: OutlineType, downsample: OutlineDownsample) -> OutlineSettings {
+        OutlineSettings {
+            outline_type,
+            downsample,
+            ..default()
+        }
+    }
+
+    #[test]
+    fn full_window() {
+        let viewport = camera_viewport(None, primary_window(2.0)).unwrap();
+        assert_eq!(viewport.origin, UVec2::ZERO);
+        assert_eq!(viewport.size, UVec2::new(1280, 720));
+        assert_eq!(viewport.scale_factor, 2.0);
+    }
+
+    #[test]
+    fn invalid_viewports() {
+        let outside = Viewport {
+            physical_position: UVec2::new(640, 0),
+            physical_size: UVec2::new(641, 720),
+            ..default()
+        };
+        assert_eq!(camera_viewport(Some(outside), primary_window(1.0)), None);
+        assert_eq!(
+            OutlineViewport::new(UVec2::ZERO, UVec2::new(0, 720), UVec2::new(1280, 720)),
+            None
+        );
+    }
+
+    #[test]
+    fn split_screen_halves_cover_the_window() {
+        let [left, right] = split_screen(UVec2::new(1280, 720))
+            .map(|viewport| camera_viewport(Some(viewport), primary_window(1.0)).unwrap());
+        assert_eq!(left.origin, UVec2::ZERO);
+        assert_eq!(right.origin, UVec2::new(640, 0));
+        // The halves meet without overlapping
+        assert_eq!(left.origin.x + left.size.x, right.origin.x);
+        assert_eq!(right.origin + right.size, UVec2::new(1280, 720));
+
+        // The textures of every pass only cover their half
+        for viewport in [left, right] {
+            for outline_type in [OutlineType::MaxFilter, OutlineType::Jfa] {
+                let settings = settings(outline_type, OutlineDownsample::None);
+                assert_eq!(viewport.outline_texture_size(&settings), viewport.size);
+            }
+        }
+    }
+
+    #[test]
+    fn secondary_window() {
+        let secondary_window = |app: &mut App| {
+            app.world
+                .spawn((window(1280.0, 720.0, 1.0), PrimaryWindow));
+            let secondary = app.world.spawn(window(800.0, 600.0, 1.5)).id();
+            RenderTarget::Window(WindowRef::Entity(secondary))
+        };
+        let [_, right] = split_screen(UVec2::new(800, 600));
+        let viewport = camera_viewport(Some(right), secondary_window).unwrap();
+        assert_eq!(viewport.origin, UVec2::new(400, 0));
+        assert_eq!(viewport.size, UVec2::new(400, 600));
+        assert_eq!(viewport.scale_factor, 1.5);
+
+        let max_filter = settings(OutlineType::MaxFilter, OutlineDownsample::None);
+        assert_eq!(viewport.outline_texture_size(&max_filter), viewport.size);
+        let blur = settings(OutlineType::BoxBlur, OutlineDownsample::Half);
+        assert_eq!(viewport.outline_texture_size(&blur), UVec2::new(200, 300));
+    }
+
+    #[test]
+    fn image_target() {
+        let image_target = |app: &mut App| {
+            app.world
+                .spawn((window(1280.0, 720.0, 2.0), PrimaryWindow));
+            let image = Image::new_fill(
+                Extent3d {
+                    width: 512,
+                    height: 256,
+                    depth_or_array_layers: 1,
+                },
+                TextureDimension::D2,
+                &[0; 4],
+                TextureFormat::Rgba8UnormSrgb,
+            );
+            RenderTarget::Image(app.world.resource_mut::<Assets<Image>>().add(image))
+        };
+        // The image has its own size, the window doesn't matter
+        let viewport = camera_viewport(None, image_target).unwrap();
+        assert_eq!(viewport.origin, UVec2::ZERO);
+        assert_eq!(viewport.size, UVec2::new(512, 256));
+        assert_eq!(viewport.scale_factor, 1.0);
+
+        let jfa = settings(OutlineType::Jfa, OutlineDownsample::None);
+        assert_eq!(viewport.outline_texture_size(&jfa), viewport.size);
+        let blur = settings(OutlineType::GaussianBlur, OutlineDownsample::Quarter);
+        assert_eq!(viewport.outline_texture_size(&blur), UVec2::new(128, 64));
+
+        // A viewport fitting in the window but not in the image
+        let outside = Viewport {
+            physical_position: UVec2::new(256, 0),
+            physical_size: UVec2::new(512, 256),
+            ..default()
+        };
+        assert_eq!(camera_viewport(Some(outside), image_target), None);
+    }
+
+    #[test]
+    fn downsampled_textures_cover_the_viewport() {
+        // An odd window splits into halves of different sizes
+        let target = UVec2::new(1281, 721);
+        for viewport in split_screen(target) {
+            let viewport = OutlineViewport::new(
+                viewport.physical_position,
+                viewport.physical_size,
+                target,
+            )
+            .unwrap();
+            for downsample in [
+                OutlineDownsample::None,
+                OutlineDownsample::Half,
+                OutlineDownsample::Quarter,
+            ] {
+                for outline_type in [OutlineType::BoxBlur, OutlineType::GaussianBlur] {
+                    let size = viewport.outline_texture_size(&settings(outline_type, downsample));
+                    assert!((size * downsample.factor()).cmpge(viewport.size).all());
+                    assert!(((size - UVec2::ONE) * downsample.factor())
+                        .cmplt(viewport.size)
+                        .all());
+                }
+                // Only the blurs are downsampled
+                let max_filter = settings(OutlineType::MaxFilter, downsample);
+                assert_eq!(viewport.outline_texture_size(&max_filter), viewport.size);
+            }
+        }
+    }
+}
@@ -0,0 +1,158 @@
+use bevy::prelude::*;
+
+use crate::{viewport::OutlineViewport, OutlineWidthUnit};
+
+/// The projection divides by the depth, `w_axis.w` is 1 for orthographic projections
+pub fn is_perspective(projection: Mat4) -> bool {
+    projection.w_axis.w == 0.0
+}
+
+/// Pixels covered by a unit of width in the given [`OutlineWidthUnit`].
+///
+/// With a perspective projection, world units are also divided by the depth of the outlined fragment.
+pub fn pixels_per_unit(
+    unit: OutlineWidthUnit,
+    projection: Mat4,
+    viewport: &OutlineViewport,
+) -> f32 {
+    match unit {
+        OutlineWidthUnit::PhysicalPixels => 1.0,
+        OutlineWidthUnit::LogicalPixels => viewport.scale_factor,
+        // The clip space spans 2 units across the height of the viewport
+        OutlineWidthUnit::World => projection.y_axis.y * 0.5 * viewport.size.y as f32,
+    }
+}
+
+/// Widest outline in physical pixels of a width in world units seen by a perspective camera.
+///
+/// Must match the documentation of [`OutlineWidthUnit::World`].
+pub const MAX_PERSPECTIVE_WORLD_WIDTH: f32 = 64.0;
+
+/// Largest width in pixels of an outline of the given size, the filters never need to look further.
+///
+/// The passes clamp the wider outlines to it. Nothing is closer to a perspective camera than its near plane,
+/// the outlines in world units are also capped to [`MAX_PERSPECTIVE_WORLD_WIDTH`] since the near plane is
+/// usually so close that the filters would look hundreds of pixels away. An outline never needs to be
+/// wider than the viewport.
+pub fn max_pixel_width(
+    size: f32,
+    unit: OutlineWidthUnit,
+    projection: Mat4,
+    viewport: &OutlineViewport,
+) -> f32 {
+    let mut width = size.max(0.0) * pixels_per_unit(unit, projection, viewport);
+    if unit == OutlineWidthUnit::World && is_perspective(projection) {
+        // Bevy's reversed infinite perspective stores the near plane there
+        width = (width / projection.w_axis.z).min(MAX_PERSPECTIVE_WORLD_WIDTH);
+    }
+    width.min(viewport.size.as_vec2().length())
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::render::camera::CameraProjection;
+
+    use super::*;
+
+    fn viewport(scale_factor: f32) -> OutlineViewport {
+        OutlineViewport {
+            scale_factor,
+            ..OutlineViewport::new(UVec2::ZERO, UVec2::new(1280, 720), UVec2::new(1280, 720))
+                .unwrap()
+        }
+    }
+
+    fn perspective() -> Mat4 {
+        PerspectiveProjection {
+            near: 0.5,
+            ..default()
+        }
+        .get_projection_matrix()
+    }
+
+    /// Projects a segment of the given length, perpendicular to the view at the given depth, and returns its length in pixels
+    fn projected_pixels(projection: Mat4, length: f32, depth: f32) -> f32 {
+        let project = |y: f32| projection.project_point3(Vec3::new(0.0, y, -depth)).y;
+        (project(length) - project(0.0)) * 0.5 * 720.0
+    }
+
+    #[test]
+    fn pixels() {
+        let projection = perspective();
+        assert_eq!(
+            pixels_per_unit(OutlineWidthUnit::PhysicalPixels, projection, &viewport(2.0)),
+            1.0
+        );
+        assert_eq!(
+            pixels_per_unit(OutlineWidthUnit::LogicalPixels, projection, &viewport(2.0)),
+            2.0
+        );
+        assert_eq!(
+            max_pixel_width(
+                8.0,
+                OutlineWidthUnit::LogicalPixels,
+                projection,
+                &viewport(1.5)
+            ),
+            12.0
+        );
+    }
+
+    #[test]
+    fn world_units_with_perspective() {
+        let projection = perspective();
+        assert!(is_perspective(projection));
+        let pixels_per_unit = pixels_per_unit(OutlineWidthUnit::World, projection, &viewport(1.0));
+        for depth in [0.5, 2.0, 10.0] {
+            let expected = projected_pixels(projection, 0.1, depth);
+            assert!((0.1 * pixels_per_unit / depth - expected).abs() < 1e-3);
+        }
+        // The widest outline is at the near plane
+        let max = max_pixel_width(0.01, OutlineWidthUnit::World, projection, &viewport(1.0));
+        assert!(max < MAX_PERSPECTIVE_WORLD_WIDTH);
+        assert!((max - projected_pixels(projection, 0.01, 0.5)).abs() < 1e-3);
+    }
+
+    #[test]
+    fn world_units_with_perspective_are_capped() {
+        // A typical near plane would make the filters look hundreds of pixels away
+        let projection = PerspectiveProjection::default().get_projection_matrix();
+        assert!(projected_pixels(projection, 0.1, 0.1) > 500.0);
+        let max = max_pixel_width(0.1, OutlineWidthUnit::World, projection, &viewport(1.0));
+        assert_eq!(max, MAX_PERSPECTIVE_WORLD_WIDTH);
+
+        // Orthographic projections don't depend on the depth, they aren't capped
+        let mut orthographic = OrthographicProjection::default();
+        orthographic.update(1280.0, 720.0);
+        let max = max_pixel_width(
+            100.0,
+            OutlineWidthUnit::World,
+            orthographic.get_projection_matrix(),
+            &viewport(1.0),
+        );
+        assert!((max - 100.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn world_units_with_orthographic() {
+        let mut orthographic = OrthographicProjection::default();
+        orthographic.update(1280.0, 720.0);
+        let projection = orthographic.get_projection_matrix();
+        assert!(!is_perspective(projection));
+        // The default orthographic projection maps a world unit to a pixel
+        let pixels_per_unit = pixels_per_unit(OutlineWidthUnit::World, projection, &viewport(1.0));
+        assert!((pixels_per_unit - 1.0).abs() < 1e-5);
+        assert!((pixels_per_unit - projected_pixels(projection, 1.0, 5.0)).abs() < 1e-3);
+    }
+
+    #[test]
+    fn max_width_fits_the_viewport() {
+        let max = max_pixel_width(
+            10000.0,
+            OutlineWidthUnit::PhysicalPixels,
+            perspective(),
+            &viewport(1.0),
+        );
+        assert_eq!(max, Vec2::new(1280.0, 720.0).length());
+    }
+}